@@ -0,0 +1,378 @@
+//! # Draft
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) in which subjects take turns picking seats, also for subjects [demanding](crate::Subject::demand) several seats.
+use super::Assigner;
+use super::AssignmentError;
+use super::GroupRegistry;
+use super::GrowingGroupRegistry;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::errors::{self, CapacityError};
+use crate::assignment::random::Rng;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+use std::collections::HashMap;
+
+/// Decides in which order the subjects pick in the first round of a [draft](Draft).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DraftOrder {
+    /// The subjects pick in the order they were given in.
+    #[default]
+    Given,
+    /// The subjects pick in the reverse of the order they were given in.
+    Reversed,
+    /// The subjects pick in a pseudo random order determined by the seed.
+    Seeded(u64),
+}
+
+/// Subjects together with their seats, where a subject may have seats in several groups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiAssignment {
+    subject_ids_to_group_ids: HashMap<u32, Vec<u32>>,
+    group_ids_to_subjects_ids: HashMap<u32, Vec<u32>>,
+}
+
+impl MultiAssignment {
+    /// Get the ids of the groups the given subject has a seat in, in the order the seats were picked.
+    pub fn subject_to_group_ids<S: Subject>(&self, subject: &S) -> Option<&Vec<u32>> {
+        self.subject_ids_to_group_ids.get(&subject.id())
+    }
+
+    /// Get the ids of the subjects with a seat in the given group, in the order the seats were picked.
+    pub fn group_to_subjects_ids<G: Group>(&self, group: &G) -> Option<&Vec<u32>> {
+        self.group_ids_to_subjects_ids.get(&group.id())
+    }
+
+    /// Get the ids of the groups the subject with the given id has a seat in, in the order the seats were picked.
+    pub fn subject_id_to_group_ids(&self, subject_id: &u32) -> Option<&Vec<u32>> {
+        self.subject_ids_to_group_ids.get(subject_id)
+    }
+
+    /// Get the ids of the subjects with a seat in the group with the given id, in the order the seats were picked.
+    pub fn group_id_to_subjects_ids(&self, group_id: &u32) -> Option<&Vec<u32>> {
+        self.group_ids_to_subjects_ids.get(group_id)
+    }
+
+    /// An iterator over the pairs (subject id, group id) of every seat, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.subject_ids_to_group_ids
+            .iter()
+            .flat_map(|(subject_id, group_ids)| {
+                group_ids
+                    .iter()
+                    .map(move |group_id| (*subject_id, *group_id))
+            })
+    }
+}
+
+/// Assigns by a snake draft: in every round the subjects pick their most preferred group with room for them in turn,
+/// where the order of the subjects is reversed after every round, until every subject has as many seats as it [demands](crate::Subject::demand).
+/// A subject never picks a seat in a group it already has a seat in.
+///
+/// Seats [reserved](crate::Group::reserved_seats) for a category are only available to eligible subjects at first.
+/// Once no subject can pick any more, the unused reserved seats are released and the draft continues.
+///
+/// As an [assigner](Assigner) every subject picks a single seat in the given order, regardless of its demand.
+/// Use [draft](Draft::draft) for subjects demanding several seats.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Draft, DraftOrder, TieBreaking};
+/// let preferences = [(101, 0), (102, 1), (103, 2)].iter().cloned().collect();
+/// let subjects = [
+///     DefaultSubject::new(1, preferences, 3).with_demand(2),
+///     DefaultSubject::new(2, [(101, 0), (103, 1), (102, 2)].iter().cloned().collect(), 3).with_demand(2),
+/// ];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1), DefaultGroup::new(103, 2)];
+/// let allocation = Draft::draft(&subjects, &groups, DraftOrder::Given, TieBreaking::default()).unwrap();
+/// // The first subject takes the first group, the second subject the third group.
+/// // In the second round the second subject picks first and takes the last seat of the second group.
+/// assert_eq!(Some(&vec![101, 103]), allocation.subject_to_group_ids(&subjects[0]));
+/// assert_eq!(Some(&vec![103, 102]), allocation.subject_to_group_ids(&subjects[1]));
+/// ```
+pub struct Draft {}
+
+impl Assigner for Draft {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        let (group_registries, _) =
+            draft_rounds(subjects, groups, |_| 1, DraftOrder::Given, tie_breaking);
+        Ok(super::assign_from_group_registries(
+            group_registries
+                .into_iter()
+                .map(|x| x.group_registry)
+                .collect(),
+        ))
+    }
+}
+
+impl Draft {
+    /// Let the given subjects draft seats in the given groups, starting in the given order and deciding between groups a subject is equally dissatisfied with
+    /// according to the tie-breaking policy.
+    ///
+    /// An error is returned if the groups do not have enough seats for the total demand, or if some subject can not get as many seats as it demands.
+    pub fn draft<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        order: DraftOrder,
+        tie_breaking: TieBreaking,
+    ) -> Result<MultiAssignment, AssignmentError> {
+        errors::unique_ids(
+            subjects.iter().map(|s| s.id()),
+            groups.iter().map(|g| g.id()),
+        )?;
+        let total_demand = subjects.iter().map(|s| s.demand() as usize).sum();
+        errors::sufficient_capacity(total_demand, groups)?;
+        let (_, picks) = draft_rounds(subjects, groups, |s| s.demand(), order, tie_breaking);
+        let mut subject_ids_to_group_ids: HashMap<u32, Vec<u32>> =
+            subjects.iter().map(|s| (s.id(), Vec::new())).collect();
+        let mut group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> =
+            groups.iter().map(|g| (g.id(), Vec::new())).collect();
+        for (i, j) in picks {
+            let (subject_id, group_id) = (subjects[i].id(), groups[j].id());
+            subject_ids_to_group_ids
+                .entry(subject_id)
+                .or_default()
+                .push(group_id);
+            group_ids_to_subjects_ids
+                .entry(group_id)
+                .or_default()
+                .push(subject_id);
+        }
+        if let Some(subject) = subjects
+            .iter()
+            .find(|s| subject_ids_to_group_ids[&s.id()].len() < s.demand() as usize)
+        {
+            return Err(AssignmentError::Infeasible {
+                reason: format!(
+                    "The subject with id {} gets fewer than the {} seats it demands",
+                    subject.id(),
+                    subject.demand()
+                ),
+            });
+        }
+        Ok(MultiAssignment {
+            subject_ids_to_group_ids,
+            group_ids_to_subjects_ids,
+        })
+    }
+}
+
+// A seat picked by a subject, given by the index of the subject and the index of the group.
+type Pick = (usize, usize);
+
+// Runs the draft, where every subject picks until it has the given number of seats or no group has room for it.
+// Besides the group registries, the picks are returned in the order they were made.
+fn draft_rounds<'a, S: Subject, G: Group>(
+    subjects: &'a [S],
+    groups: &'a [G],
+    demand: impl Fn(&S) -> u32,
+    order: DraftOrder,
+    tie_breaking: TieBreaking,
+) -> (Vec<DraftingGroupRegistry<'a, S, G>>, Vec<Pick>) {
+    let tie_breaker = TieBreaker::new(tie_breaking, groups);
+    let mut group_registries: Vec<_> = groups
+        .iter()
+        .map(|g| DraftingGroupRegistry {
+            group_registry: SimpleGroupRegistry::new(g, Vec::new()),
+        })
+        .collect();
+    let mut turn_order: Vec<usize> = (0..subjects.len()).collect();
+    match order {
+        DraftOrder::Given => {}
+        DraftOrder::Reversed => turn_order.reverse(),
+        DraftOrder::Seeded(seed) => {
+            let mut rng = Rng::new(seed);
+            for i in (1..turn_order.len()).rev() {
+                turn_order.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+            }
+        }
+    }
+    let mut seats = vec![0_u32; subjects.len()];
+    let mut picks = Vec::new();
+    let mut reserved_seats_released = false;
+    loop {
+        let mut picked = false;
+        for i in turn_order.iter().copied() {
+            if seats[i] >= demand(&subjects[i]) {
+                continue;
+            }
+            let members: Vec<usize> = group_registries
+                .iter()
+                .map(|x| x.number_of_members())
+                .collect();
+            group_registries = super::subject_to_best_available_group_registry(
+                &subjects[i],
+                group_registries,
+                &tie_breaker,
+            );
+            if let Some(j) =
+                (0..groups.len()).find(|j| group_registries[*j].number_of_members() > members[*j])
+            {
+                seats[i] += 1;
+                picks.push((i, j));
+                picked = true;
+            }
+        }
+        turn_order.reverse();
+        if !picked {
+            if reserved_seats_released || groups.iter().all(|x| x.reserved_seats().is_empty()) {
+                break;
+            }
+            for group_registry in group_registries.iter_mut() {
+                group_registry.release_reserved_seats();
+            }
+            reserved_seats_released = true;
+        }
+    }
+    (group_registries, picks)
+}
+
+// Prevents subjects from picking a second seat in the same group.
+struct DraftingGroupRegistry<'a, S: Subject, G: Group> {
+    group_registry: SimpleGroupRegistry<'a, S, G>,
+}
+impl<'a, S: Subject, G: Group> Group for DraftingGroupRegistry<'a, S, G> {
+    fn id(&self) -> u32 {
+        self.group_registry.id()
+    }
+
+    fn capacity(&self) -> u32 {
+        self.group_registry.capacity()
+    }
+
+    fn has_unlimited_capacity(&self) -> bool {
+        self.group_registry.has_unlimited_capacity()
+    }
+}
+
+impl<'a, S: Subject, G: Group> GroupRegistry for DraftingGroupRegistry<'a, S, G> {
+    fn subjects_ids_to_group_id(&self) -> HashMap<u32, u32> {
+        self.group_registry.subjects_ids_to_group_id()
+    }
+
+    fn group_id_to_subject_ids(&self) -> HashMap<u32, Vec<u32>> {
+        self.group_registry.group_id_to_subject_ids()
+    }
+
+    fn full(&self) -> bool {
+        self.group_registry.full()
+    }
+
+    fn number_of_members(&self) -> usize {
+        self.group_registry.number_of_members()
+    }
+}
+
+impl<'a, S: Subject, G: Group> GrowingGroupRegistry<'a, S> for DraftingGroupRegistry<'a, S, G> {
+    fn register_subject(&mut self, subject: &'a S) -> Result<(), CapacityError> {
+        if !self.accepts(subject) {
+            return Err(CapacityError {});
+        }
+        self.group_registry.register_subject(subject)
+    }
+
+    fn accepts(&self, subject: &S) -> bool {
+        self.group_registry.accepts(subject)
+            && self
+                .group_registry
+                .subjects
+                .iter()
+                .all(|x| x.id() != subject.id())
+    }
+
+    fn release_reserved_seats(&mut self) {
+        self.group_registry.release_reserved_seats();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::DefaultSubject;
+
+    #[test]
+    fn draft_snakes() {
+        let group_ids = [101_u32, 102, 103, 104];
+        // Everyone has the same preferences and demands two seats
+        let preferences: HashMap<u32, u32> = group_ids
+            .iter()
+            .enumerate()
+            .map(|(rank, id)| (*id, rank as u32))
+            .collect();
+        let subjects: Vec<DefaultSubject> = (1..=3)
+            .map(|id| DefaultSubject::new(id, preferences.clone(), 9).with_demand(2))
+            .collect();
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 2))
+            .collect();
+        let draft =
+            |order| Draft::draft(&subjects, &groups, order, TieBreaking::default()).unwrap();
+        // The last subject of the first round picks first in the second round
+        let allocation = draft(DraftOrder::Given);
+        assert_eq!(
+            Some(&vec![101, 103]),
+            allocation.subject_id_to_group_ids(&1)
+        );
+        assert_eq!(
+            Some(&vec![101, 102]),
+            allocation.subject_id_to_group_ids(&2)
+        );
+        assert_eq!(
+            Some(&vec![102, 103]),
+            allocation.subject_id_to_group_ids(&3)
+        );
+        assert_eq!(Some(&vec![3, 2]), allocation.group_id_to_subjects_ids(&102));
+        assert_eq!(Some(&vec![]), allocation.group_id_to_subjects_ids(&104));
+        let allocation = draft(DraftOrder::Reversed);
+        assert_eq!(
+            Some(&vec![101, 103]),
+            allocation.subject_id_to_group_ids(&3)
+        );
+        assert_eq!(
+            Some(&vec![102, 103]),
+            allocation.subject_id_to_group_ids(&1)
+        );
+        assert_eq!(draft(DraftOrder::Seeded(3)), draft(DraftOrder::Seeded(3)));
+        assert_eq!(6, draft(DraftOrder::Seeded(3)).iter().count());
+    }
+
+    #[test]
+    fn draft_unmet_demand() {
+        let subjects = [
+            DefaultSubject::new(1, HashMap::new(), 0).with_demand(2),
+            DefaultSubject::new(2, HashMap::new(), 0).with_demand(2),
+        ];
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 2)];
+        assert!(matches!(
+            Draft::draft(
+                &subjects,
+                &groups,
+                DraftOrder::Given,
+                TieBreaking::default()
+            ),
+            Err(AssignmentError::InsufficientCapacity {
+                required: 4,
+                available: 3
+            })
+        ));
+        // Enough seats in total, but the second subject can not get two seats in different groups
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 3)];
+        assert!(matches!(
+            Draft::draft(
+                &subjects,
+                &groups,
+                DraftOrder::Given,
+                TieBreaking::default()
+            ),
+            Err(AssignmentError::Infeasible { .. })
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! # Egalitarian
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) looking after the worst-off subject first.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::min_cost_flow::cheapest_placement;
+use crate::groups::effective_capacity;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+
+/// Assigns such that the highest dissatisfaction of any subject is as low as possible, and subject to that the total dissatisfaction is as low as possible.
+///
+///
+/// The highest dissatisfaction is found by a binary search over the dissatisfaction ratings occurring among the subjects,
+/// checking for each candidate whether every subject can be placed in a group it is at most that dissatisfied with.
+/// Then the subjects are placed among those groups so that the total dissatisfaction is as low as possible.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Assigner, Egalitarian, Incremental};
+/// let subjects = [
+///     DefaultSubject::new(1, [(101, 0), (102, 2)].iter().cloned().collect(), 3),
+///     DefaultSubject::new(2, [(101, 2), (102, 3)].iter().cloned().collect(), 3),
+/// ];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// // The lowest total dissatisfaction (3) leaves the second subject with a dissatisfaction of 3
+/// let cheapest = Incremental::assign(&subjects, &groups).unwrap();
+/// assert_eq!(Some(&102), cheapest.subject_to_group_id(&subjects[1]));
+/// // Whereas nobody has a dissatisfaction above 2 if the first subject gives up its first choice
+/// let egalitarian = Egalitarian::assign(&subjects, &groups).unwrap();
+/// assert_eq!(Some(&101), egalitarian.subject_to_group_id(&subjects[1]));
+/// ```
+pub struct Egalitarian {}
+
+impl Assigner for Egalitarian {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        let dissatisfaction: Vec<Vec<u32>> = subjects
+            .iter()
+            .map(|s| groups.iter().map(|g| s.dissatisfaction(&g.id())).collect())
+            .collect();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let mut thresholds: Vec<u32> = dissatisfaction.iter().flatten().copied().collect();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        let everyone_placed = |threshold: u32| {
+            cheapest_placement(
+                subjects.len(),
+                &capacities,
+                |i, j| (dissatisfaction[i][j] <= threshold).then_some(0),
+                |_i, _j| 0,
+                None,
+            )
+            .iter()
+            .all(|x| x.is_some())
+        };
+        // Capacity is sufficient, hence every subject can be placed when the largest threshold is allowed.
+        let threshold = thresholds
+            .get(thresholds.partition_point(|t| !everyone_placed(*t)))
+            .copied()
+            .unwrap_or(u32::MAX);
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let ranks: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|s| tie_breaker.ranks(s.id(), groups))
+            .collect();
+        let placement: Vec<usize> = cheapest_placement(
+            subjects.len(),
+            &capacities,
+            |i, j| (dissatisfaction[i][j] <= threshold).then_some(dissatisfaction[i][j] as i64),
+            |i, j| ranks[i][j],
+            tie_breaker.seat_costs().as_deref(),
+        )
+        .into_iter()
+        .collect::<Option<_>>()
+        .ok_or_else(|| AssignmentError::Infeasible {
+            reason: "The subjects can not all be placed within the lowest feasible threshold"
+                .to_string(),
+        })?;
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, group_index) in subjects.iter().zip(placement) {
+            group_registries[group_index].subjects.push(subject);
+        }
+        Ok(super::assign_from_group_registries(group_registries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::Incremental;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn assign_minimises_highest_dissatisfaction() {
+        let group_ids = [101_u32, 102, 103];
+        // The cheapest assignment gives the first two subjects their first choices, leaving the third subject with a dissatisfaction of 3
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[1], group_ids[0], group_ids[2]]),
+            TestSubject::new(3, vec![900, 901, group_ids[0], group_ids[2]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+        ];
+        let highest = |assignment: &Assignment| {
+            subjects
+                .iter()
+                .map(|s| s.dissatisfaction(assignment.subject_to_group_id(s).unwrap()))
+                .max()
+                .unwrap()
+        };
+        let total = |assignment: &Assignment| -> u32 {
+            subjects
+                .iter()
+                .map(|s| s.dissatisfaction(assignment.subject_to_group_id(s).unwrap()))
+                .sum()
+        };
+        let cheapest = Incremental::assign(&subjects, &groups).unwrap();
+        let egalitarian = Egalitarian::assign(&subjects, &groups).unwrap();
+        assert_eq!((3, 3), (highest(&cheapest), total(&cheapest)));
+        assert_eq!((2, 4), (highest(&egalitarian), total(&egalitarian)));
+        assert!(egalitarian.validate(&subjects, &groups).is_empty());
+    }
+}
@@ -1,8 +1,9 @@
 // This module implements an [assigner](crate::assignment::assigners::Assigner) according to the "first come first served" principle.
 use super::Assigner;
+use super::AssignmentError;
 use super::GrowingGroupRegistry;
 use super::SimpleGroupRegistry;
-use super::TotalCapacityError;
+use super::{TieBreaker, TieBreaking};
 use crate::subjects::Subject;
 use crate::{assignment::Assignment, groups::Group};
 
@@ -10,11 +11,18 @@ use crate::{assignment::Assignment, groups::Group};
 pub struct FirstComeFirstServed {}
 impl Assigner for FirstComeFirstServed {
     /// The subjects get assigned to their most preferred available group in turn.
-    fn assign<S: Subject, G: Group>(
+    ///
+    /// Seats [reserved](crate::Group::reserved_seats) for a category are only available to eligible subjects at first.
+    /// Once every subject has had their turn the unused reserved seats are released, and subjects
+    /// (in the given order) may move to a group they prefer that has become available to them.
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
         subjects: &[S],
         groups: &[G],
-    ) -> Result<Assignment, TotalCapacityError> {
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
         Self::sufficient_capacity(subjects, groups)?;
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
         let group_managers: Vec<_> = groups
             .iter()
             .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
@@ -24,7 +32,13 @@ impl Assigner for FirstComeFirstServed {
             subjects_to_best_available_group_registry_by_the_first_come_first_served_principle(
                 subjects,
                 group_managers,
+                &tie_breaker,
             );
+        let group_managers = if groups.iter().any(|x| !x.reserved_seats().is_empty()) {
+            super::release_reserved_seats(subjects, group_managers, &tie_breaker)
+        } else {
+            group_managers
+        };
 
         Ok(super::assign_from_group_registries(group_managers))
     }
@@ -37,10 +51,11 @@ fn subjects_to_best_available_group_registry_by_the_first_come_first_served_prin
 >(
     subjects: &'a [S],
     mut group_registries: Vec<M>,
+    tie_breaker: &TieBreaker,
 ) -> Vec<M> {
     for subject in subjects.iter() {
         group_registries =
-            super::subject_to_best_available_group_registry(subject, group_registries);
+            super::subject_to_best_available_group_registry(subject, group_registries, tie_breaker);
     }
     group_registries
 }
@@ -97,4 +112,61 @@ mod tests {
         );
         assert!(group_identifiers_to_subjects_identifiers[&group_ids[2]].contains(&subject_ids[3]));
     }
+
+    #[test]
+    fn assign_reserved_seats() {
+        use crate::groups::SeatReservation;
+        use crate::subjects::DefaultSubject;
+        let status = 1_u32;
+        let exchange_student = 3_u32;
+        let group_ids = [101_u32, 102];
+        let preferences: HashMap<u32, u32> = [(group_ids[0], 0), (group_ids[1], 1)]
+            .iter()
+            .cloned()
+            .collect();
+        let local = |id: u32| DefaultSubject::new(id, preferences.clone(), 2);
+        let exchange = |id: u32| {
+            local(id).with_categories([(status, exchange_student)].iter().cloned().collect())
+        };
+        let groups = [
+            DefaultGroup::new(group_ids[0], 2).with_reserved_seats(vec![SeatReservation::new(
+                status,
+                exchange_student,
+                1,
+            )]),
+            DefaultGroup::new(group_ids[1], 2),
+        ];
+
+        // The reserved seat is kept for the exchange student arriving last
+        let subjects = [local(1), local(2), exchange(3)];
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[2])
+        );
+
+        // Without eligible subjects the reserved seat is released and taken by the second subject
+        let subjects = [local(1), local(2), local(3)];
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[2])
+        );
+    }
 }
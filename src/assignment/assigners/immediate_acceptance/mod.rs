@@ -0,0 +1,269 @@
+//! # Immediate acceptance
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) implementing the immediate acceptance mechanism, also known as the Boston mechanism.
+use super::Assigner;
+use super::AssignmentError;
+use super::GroupRegistry;
+use super::GrowingGroupRegistry;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::envy::PriorityPolicy;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+
+/// Assigns according to the immediate acceptance (Boston) mechanism.
+///
+///
+/// In the first round every subject applies to its first choice (the groups it is least dissatisfied with), and every group accepts as many applicants as it has seats,
+/// in the order of their [priority](PriorityPolicy). In the second round the subjects that have not been accepted yet apply to their second choice, and so on.
+/// Unlike for [ProposeAndReject](crate::assigners::ProposeAndReject) acceptance is final: a seat taken in an early round is never given to a subject with a higher priority applying later.
+///
+/// When a choice consists of several equally good groups, every applicant applies to the best group among those with room for it according to the [tie-breaking policy](TieBreaking),
+/// where the applicants of the same group count as members. Every group accepts its applicants in the order of their priority until it is full,
+/// and the rejected applicants then apply to the remaining groups of their choice.
+///
+/// Seats [reserved](crate::Group::reserved_seats) for a category are only available to eligible subjects at first. Once all rounds are over, the unused reserved seats are released
+/// and the remaining subjects apply once more, starting from their first choice.
+///
+/// [assign](Assigner::assign) gives priority according to the [default policy](PriorityPolicy::Dissatisfaction),
+/// use [assign_with_priorities](ImmediateAcceptance::assign_with_priorities) for other policies.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{ImmediateAcceptance, TieBreaking};
+/// use group_assignment::envy::PriorityPolicy;
+/// let subjects = [
+///     DefaultSubject::new(1, [(101, 0), (102, 1), (103, 2)].iter().cloned().collect(), 3),
+///     DefaultSubject::new(2, [(101, 0), (102, 1), (103, 2)].iter().cloned().collect(), 3),
+///     DefaultSubject::new(3, [(102, 0), (101, 1), (103, 2)].iter().cloned().collect(), 3),
+/// ];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1), DefaultGroup::new(103, 1)];
+/// let assignment = ImmediateAcceptance::assign_with_priorities(
+///     &subjects,
+///     &groups,
+///     PriorityPolicy::SubjectOrder,
+///     TieBreaking::default(),
+/// )
+/// .unwrap();
+/// // The second subject loses the first group to the first subject, and the third subject took the second group in the first round
+/// assert_eq!(Some(&103), assignment.subject_to_group_id(&subjects[1]));
+/// assert_eq!(Some(&102), assignment.subject_to_group_id(&subjects[2]));
+/// ```
+pub struct ImmediateAcceptance {}
+
+impl Assigner for ImmediateAcceptance {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::assign_with_priorities(subjects, groups, PriorityPolicy::default(), tie_breaking)
+    }
+}
+
+impl ImmediateAcceptance {
+    /// Assign the given subjects to the given groups, where groups accept applicants in the order given by the priority policy
+    /// (among subjects with equal priority, the one given first) and subjects decide between equally good groups according to the tie-breaking policy.
+    pub fn assign_with_priorities<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        priority: PriorityPolicy,
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        // For every subject its choices, each given by the dissatisfaction shared by the groups of the choice, from first to last
+        let choices: Vec<Vec<u32>> = subjects
+            .iter()
+            .map(|s| {
+                let mut ratings: Vec<u32> =
+                    groups.iter().map(|g| s.dissatisfaction(&g.id())).collect();
+                ratings.sort_unstable();
+                ratings.dedup();
+                ratings
+            })
+            .collect();
+        let number_of_rounds = choices.iter().map(|x| x.len()).max().unwrap_or(0);
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        let mut placed = vec![false; subjects.len()];
+        for round in 0..number_of_rounds {
+            application_round(
+                subjects,
+                &choices,
+                round,
+                priority,
+                &tie_breaker,
+                &mut group_registries,
+                &mut placed,
+            );
+        }
+        if placed.iter().any(|x| !x) {
+            for group_registry in group_registries.iter_mut() {
+                group_registry.release_reserved_seats();
+            }
+            for round in 0..number_of_rounds {
+                application_round(
+                    subjects,
+                    &choices,
+                    round,
+                    priority,
+                    &tie_breaker,
+                    &mut group_registries,
+                    &mut placed,
+                );
+            }
+        }
+        Ok(super::assign_from_group_registries(group_registries))
+    }
+}
+
+// Lets every subject that is not placed yet apply to the groups of the given choice, until none of them can be accepted any more.
+fn application_round<'a, S: Subject, G: Group>(
+    subjects: &'a [S],
+    choices: &[Vec<u32>],
+    round: usize,
+    priority: PriorityPolicy,
+    tie_breaker: &TieBreaker,
+    group_registries: &mut [SimpleGroupRegistry<'a, S, G>],
+    placed: &mut [bool],
+) {
+    loop {
+        // Every applicant applies to the best group of its choice with room for it, counting the earlier applicants as members
+        let mut applicants: Vec<Vec<usize>> = vec![Vec::new(); group_registries.len()];
+        for (i, subject) in subjects.iter().enumerate().filter(|(i, _)| !placed[*i]) {
+            let rating = match choices[i].get(round) {
+                Some(rating) => *rating,
+                None => continue,
+            };
+            if let Some(j) = group_registries
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| subject.dissatisfaction(&x.id()) == rating && x.accepts(subject))
+                .min_by_key(|(j, x)| {
+                    tie_breaker.key(
+                        subject.id(),
+                        x.id(),
+                        x.number_of_members() + applicants[*j].len(),
+                    )
+                })
+                .map(|(j, _)| j)
+            {
+                applicants[j].push(i);
+            }
+        }
+        // Every group accepts its applicants in the order of their priority while it has room for them.
+        // Rejected applicants apply to the remaining groups of their choice in the next iteration.
+        let mut accepted_any = false;
+        for (group_registry, mut group_applicants) in group_registries.iter_mut().zip(applicants) {
+            let id = group_registry.id();
+            group_applicants.sort_by_key(|i| (priority.key(&subjects[*i], *i, &id), *i));
+            for i in group_applicants {
+                if group_registry.register_subject(&subjects[i]).is_ok() {
+                    placed[i] = true;
+                    accepted_any = true;
+                }
+            }
+        }
+        if !accepted_any {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn assign_accepts_immediately() {
+        let group_ids = [101_u32, 102, 103];
+        // The first and the third subject both apply to the second group in the first round
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[1], group_ids[0], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[2], group_ids[1]]),
+            TestSubject::new(3, vec![group_ids[1], group_ids[2], group_ids[0]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+        ];
+        let assignment = ImmediateAcceptance::assign_with_priorities(
+            &subjects,
+            &groups,
+            PriorityPolicy::SubjectOrder,
+            TieBreaking::default(),
+        )
+        .unwrap();
+        // The second group accepts the first subject, leaving the third subject with its second choice
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+        assert_eq!(
+            Some(&group_ids[2]),
+            assignment.subject_to_group_id(&subjects[2])
+        );
+    }
+
+    #[test]
+    fn assign_admits_justified_envy() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(3, vec![group_ids[1], group_ids[0], group_ids[2]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+        ];
+        let assignment = ImmediateAcceptance::assign_with_priorities(
+            &subjects,
+            &groups,
+            PriorityPolicy::SubjectOrder,
+            TieBreaking::default(),
+        )
+        .unwrap();
+        // Having lost the first round, the second subject finds its second choice taken by the third subject despite having priority
+        assert_eq!(
+            Some(&group_ids[2]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+        let report = assignment.envy_report(&subjects, PriorityPolicy::SubjectOrder);
+        assert_eq!(1, report.justified_envies().len());
+        assert_eq!(3, report.justified_envies()[0].envied_subject_id());
+    }
+
+    #[test]
+    fn assign_seeded_priorities() {
+        let subjects: Vec<TestSubject> = (0..2)
+            .map(|id| TestSubject::new(id, vec![101, 102]))
+            .collect();
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+        let winners: Vec<u32> = (0..20)
+            .map(|seed| {
+                let assignment = ImmediateAcceptance::assign_with_priorities(
+                    &subjects,
+                    &groups,
+                    PriorityPolicy::Seeded(seed),
+                    TieBreaking::default(),
+                )
+                .unwrap();
+                assignment.group_id_to_subjects_ids(&101).unwrap()[0]
+            })
+            .collect();
+        // Every subject wins the lottery for some seed
+        assert!(winners.contains(&0) && winners.contains(&1));
+    }
+}
@@ -0,0 +1,272 @@
+//! # Incremental
+//! This module provides re-assignment after an [assignment](crate::Assignment) has been published and the subjects or groups have changed since.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::comparison::Move;
+use crate::assignment::min_cost_flow::cheapest_placement;
+use crate::groups::effective_capacity;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+use std::collections::HashMap;
+
+/// How subjects that were already placed by the previous assignment may be treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MovePolicy {
+    /// Subjects may be moved, but as few as possible.
+    MinimiseMoves,
+    /// Subjects may only be moved if their previous group no longer exists.
+    ForbidMoves,
+}
+
+/// The outcome of an incremental re-assignment: the new assignment together with the subjects that had to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reassignment {
+    assignment: Assignment,
+    moves: Vec<Move>,
+}
+
+impl Reassignment {
+    /// The new assignment.
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+
+    /// The previously placed subjects that are assigned to a different group now, in the order the subjects were given in.
+    pub fn moves(&self) -> &Vec<Move> {
+        &self.moves
+    }
+}
+
+impl From<Reassignment> for Assignment {
+    fn from(reassignment: Reassignment) -> Self {
+        reassignment.assignment
+    }
+}
+
+/// Re-assigns with minimal disruption of an existing assignment.
+///
+///
+/// Subjects that were placed by the previous assignment and whose group still exists keep their seats whenever possible.
+/// Among all assignments moving as few of these subjects as possible (or none at all, depending on the [policy](MovePolicy)),
+/// one with the lowest total dissatisfaction is chosen. Subjects that were not placed before (late registrants)
+/// and subjects whose previous group no longer exists are placed freely.
+/// Subjects that dropped out are simply left out of the given subjects.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Assigner, FirstComeFirstServed, Incremental, MovePolicy};
+/// use std::collections::HashMap;
+/// let prefer_first: HashMap<u32, u32> = [(101, 0), (102, 1)].iter().cloned().collect();
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// let subjects = vec![DefaultSubject::new(1, prefer_first.clone(), 2)];
+/// let published = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+/// // A late registrant with the same preferences arrives
+/// let subjects = vec![
+///     DefaultSubject::new(1, prefer_first.clone(), 2),
+///     DefaultSubject::new(2, prefer_first, 2),
+/// ];
+/// let reassignment =
+///     Incremental::reassign(&published, &subjects, &groups, MovePolicy::ForbidMoves).unwrap();
+/// assert!(reassignment.moves().is_empty());
+/// assert_eq!(Some(&102), reassignment.assignment().subject_to_group_id(&subjects[1]));
+/// ```
+pub struct Incremental {}
+
+impl Incremental {
+    /// Re-assign the given subjects to the given groups starting from the previous assignment.
+    ///
+    /// An error is returned if ids are not unique, if the combined capacity is insufficient, or if the subjects can not be placed without moving someone while moves are forbidden.
+    pub fn reassign<S: Subject, G: Group>(
+        previous: &Assignment,
+        subjects: &[S],
+        groups: &[G],
+        policy: MovePolicy,
+    ) -> Result<Reassignment, AssignmentError> {
+        Self::reassign_with_tie_breaking(previous, subjects, groups, policy, TieBreaking::default())
+    }
+
+    /// Like [reassign](Incremental::reassign), but among the equally good re-assignments one is chosen according to the given [tie-breaking policy](TieBreaking).
+    pub fn reassign_with_tie_breaking<S: Subject, G: Group>(
+        previous: &Assignment,
+        subjects: &[S],
+        groups: &[G],
+        policy: MovePolicy,
+        tie_breaking: TieBreaking,
+    ) -> Result<Reassignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        let group_indices: HashMap<u32, usize> = groups
+            .iter()
+            .enumerate()
+            .map(|(j, g)| (g.id(), j))
+            .collect();
+        // The index of the group every subject was placed in, if that group still exists
+        let previous_group: Vec<Option<usize>> = subjects
+            .iter()
+            .map(|s| {
+                previous
+                    .subject_ids_to_group_ids
+                    .get(&s.id())
+                    .and_then(|id| group_indices.get(id))
+                    .copied()
+            })
+            .collect();
+        // Moving a subject must cost more than any possible difference in total dissatisfaction
+        let move_penalty: i64 = 1 + subjects
+            .iter()
+            .map(|s| {
+                groups
+                    .iter()
+                    .map(|g| s.dissatisfaction(&g.id()) as i64)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum::<i64>();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let ranks: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|s| tie_breaker.ranks(s.id(), groups))
+            .collect();
+        let placement = cheapest_placement(
+            subjects.len(),
+            &capacities,
+            |i, j| {
+                let dissatisfaction = subjects[i].dissatisfaction(&groups[j].id()) as i64;
+                match (previous_group[i], policy) {
+                    (Some(p), _) if p == j => Some(dissatisfaction),
+                    (Some(_), MovePolicy::ForbidMoves) => None,
+                    (Some(_), MovePolicy::MinimiseMoves) => Some(dissatisfaction + move_penalty),
+                    (None, _) => Some(dissatisfaction),
+                }
+            },
+            |i, j| ranks[i][j],
+            tie_breaker.seat_costs().as_deref(),
+        );
+        if placement.iter().any(|x| x.is_none()) {
+            return Err(AssignmentError::Infeasible {
+                reason: "The subjects can not be placed without moving previously placed subjects"
+                    .to_string(),
+            });
+        }
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        let mut moves = Vec::new();
+        for (subject, group_index) in subjects.iter().zip(placement.into_iter().flatten()) {
+            group_registries[group_index].subjects.push(subject);
+            let to_group_id = groups[group_index].id();
+            if let Some(from_group_id) = previous.subject_ids_to_group_ids.get(&subject.id()) {
+                if *from_group_id != to_group_id {
+                    moves.push(Move::new(subject.id(), *from_group_id, to_group_id));
+                }
+            }
+        }
+        Ok(Reassignment {
+            assignment: super::assign_from_group_registries(group_registries),
+            moves,
+        })
+    }
+}
+
+impl Assigner for Incremental {
+    /// Without a previous assignment every subject is placed freely, so that the total dissatisfaction is as low as possible.
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::reassign_with_tie_breaking(
+            &Assignment::default(),
+            subjects,
+            groups,
+            MovePolicy::MinimiseMoves,
+            tie_breaking,
+        )
+        .map(Assignment::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::test_utils::assignment;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn reassign_minimises_moves_before_dissatisfaction() {
+        let group_ids = [101_u32, 102];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1]]),
+            // The late registrant only wants the first group
+            TestSubject::new(2, vec![group_ids[0]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+        ];
+        let previous = assignment(&[(1, group_ids[0])]);
+        let reassignment =
+            Incremental::reassign(&previous, &subjects, &groups, MovePolicy::MinimiseMoves)
+                .unwrap();
+        // Moving the first subject would lower the total dissatisfaction, but moves take precedence
+        assert!(reassignment.moves().is_empty());
+        assert_eq!(
+            Some(&group_ids[1]),
+            reassignment.assignment().subject_to_group_id(&subjects[1])
+        );
+    }
+
+    #[test]
+    fn reassign_moves_subjects_of_removed_groups() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[2], group_ids[1], group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[1], group_ids[0]]),
+        ];
+        // The third group has been cancelled
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+        ];
+        let previous = assignment(&[(1, group_ids[2]), (2, group_ids[1])]);
+        let reassignment =
+            Incremental::reassign(&previous, &subjects, &groups, MovePolicy::ForbidMoves).unwrap();
+        assert_eq!(
+            &vec![Move::new(1, group_ids[2], group_ids[0])],
+            reassignment.moves()
+        );
+    }
+
+    #[test]
+    fn reassign_forbidden_moves_infeasible() {
+        let group_ids = [101_u32, 102];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[0]]),
+        ];
+        // The capacity of the first group has been reduced since the previous assignment
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+        ];
+        let previous = assignment(&[(1, group_ids[0]), (2, group_ids[0])]);
+        assert!(
+            Incremental::reassign(&previous, &subjects, &groups, MovePolicy::ForbidMoves).is_err()
+        );
+        let reassignment =
+            Incremental::reassign(&previous, &subjects, &groups, MovePolicy::MinimiseMoves)
+                .unwrap();
+        assert_eq!(1, reassignment.moves().len());
+    }
+}
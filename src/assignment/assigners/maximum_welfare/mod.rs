@@ -0,0 +1,288 @@
+//! # Maximum welfare
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) optimising a configurable welfare function,
+//! trading the total dissatisfaction off against fairness towards the worst-off subjects.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::min_cost_flow::cheapest_placement;
+use crate::groups::effective_capacity;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+
+// Nash welfare costs are logarithms, which are rounded to this many binary digits after the point.
+const NASH_PRECISION: i32 = 40;
+
+/// Measures how well off the subjects are as a whole.
+///
+/// Welfare functions that weigh high dissatisfactions more heavily favour fairness over the total dissatisfaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum WelfareFunction {
+    /// The total dissatisfaction, which is to be minimised. High dissatisfactions of a few subjects are acceptable if they make many subjects a little happier.
+    Utilitarian,
+    /// The sum of the squared dissatisfactions, which is to be minimised. Raising a high dissatisfaction costs more than raising a low one.
+    SumOfSquares,
+    /// The logarithm of the product of the utilities, which is to be maximised. The utility of a subject is one more than the highest dissatisfaction
+    /// of any of the given subjects with any of the given groups that have seats, minus the subject's dissatisfaction. Hence utilities are positive, and halving
+    /// the utility of one subject is only worth it if it doubles the utility of another one.
+    /// Groups without seats are left out, so that adding a group nobody can join does not change which assignments are preferred.
+    #[default]
+    Nash,
+}
+
+impl WelfareFunction {
+    /// The value of the welfare function for the given assignment of the given subjects to the given groups.
+    /// Subjects that are not assigned to one of the given groups are not counted.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed, WelfareFunction};
+    /// let subjects = [
+    ///     DefaultSubject::new(1, [(101, 0), (102, 3)].iter().cloned().collect(), 3),
+    ///     DefaultSubject::new(2, [(101, 1), (102, 2)].iter().cloned().collect(), 3),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+    /// let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// assert_eq!(2.0, WelfareFunction::Utilitarian.value(&assignment, &subjects, &groups));
+    /// assert_eq!(4.0, WelfareFunction::SumOfSquares.value(&assignment, &subjects, &groups));
+    /// // The utilities are 4 - 0 and 4 - 2
+    /// assert!((8_f64.ln() - WelfareFunction::Nash.value(&assignment, &subjects, &groups)).abs() < 1e-9);
+    /// ```
+    pub fn value<S: Subject, G: Group>(
+        &self,
+        assignment: &Assignment,
+        subjects: &[S],
+        groups: &[G],
+    ) -> f64 {
+        let highest = highest_dissatisfaction(subjects, groups);
+        subjects
+            .iter()
+            .filter_map(|s| {
+                assignment
+                    .subject_to_group_id(s)
+                    .filter(|id| groups.iter().any(|g| g.id() == **id))
+                    .map(|id| s.dissatisfaction(id))
+            })
+            .map(|d| match self {
+                WelfareFunction::Utilitarian => d as f64,
+                WelfareFunction::SumOfSquares => (d as f64).powi(2),
+                WelfareFunction::Nash => (highest.saturating_sub(d as u64) as f64 + 1.0).ln(),
+            })
+            .sum()
+    }
+}
+
+// The highest dissatisfaction of any of the given subjects with any of the given groups that have seats, from which Nash utilities are derived.
+fn highest_dissatisfaction<S: Subject, G: Group>(subjects: &[S], groups: &[G]) -> u64 {
+    subjects
+        .iter()
+        .flat_map(|s| {
+            groups
+                .iter()
+                .filter(|g| effective_capacity(*g, subjects.len()) > 0)
+                .map(move |g| s.dissatisfaction(&g.id()) as u64)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Assigns such that a [welfare function](WelfareFunction) is optimised.
+///
+///
+/// Since the welfare of every subject only depends on its own group, the optimal assignment is found as a minimum cost flow,
+/// where the cost of placing a subject in a group is the subject's contribution to the welfare function (negated if it is to be maximised).
+/// Nash welfare is a sum of logarithms, which are rounded to a precision of about 12 decimal digits.
+///
+/// [assign](Assigner::assign) maximises the [default welfare function](WelfareFunction::Nash).
+/// Use [assign_reporting_welfare](MaximumWelfare::assign_reporting_welfare) for other welfare functions and to learn the achieved value.
+pub struct MaximumWelfare {}
+
+impl Assigner for MaximumWelfare {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::assign_reporting_welfare_with_tie_breaking(
+            subjects,
+            groups,
+            WelfareFunction::default(),
+            tie_breaking,
+        )
+        .map(|(assignment, _)| assignment)
+    }
+}
+
+impl MaximumWelfare {
+    /// Assign the given subjects to the given groups optimising the given welfare function, and report the achieved value of the welfare function.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{MaximumWelfare, WelfareFunction};
+    /// let subjects = [
+    ///     DefaultSubject::new(1, [(101, 0), (102, 4)].iter().cloned().collect(), 9),
+    ///     DefaultSubject::new(2, [(101, 2), (102, 5)].iter().cloned().collect(), 9),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+    /// // The lowest total dissatisfaction (5) leaves the second subject with a dissatisfaction of 5
+    /// let (cheapest, total) =
+    ///     MaximumWelfare::assign_reporting_welfare(&subjects, &groups, WelfareFunction::Utilitarian).unwrap();
+    /// assert_eq!((Some(&102), 5.0), (cheapest.subject_to_group_id(&subjects[1]), total));
+    /// // Whereas squaring the dissatisfactions makes the more even split (4 and 2) preferable
+    /// let (fair, squares) =
+    ///     MaximumWelfare::assign_reporting_welfare(&subjects, &groups, WelfareFunction::SumOfSquares).unwrap();
+    /// assert_eq!((Some(&101), 20.0), (fair.subject_to_group_id(&subjects[1]), squares));
+    /// ```
+    pub fn assign_reporting_welfare<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        welfare: WelfareFunction,
+    ) -> Result<(Assignment, f64), AssignmentError> {
+        Self::assign_reporting_welfare_with_tie_breaking(
+            subjects,
+            groups,
+            welfare,
+            TieBreaking::default(),
+        )
+    }
+
+    /// Like [assign_reporting_welfare](MaximumWelfare::assign_reporting_welfare), but among the optimal assignments
+    /// one is chosen according to the given [tie-breaking policy](TieBreaking).
+    pub fn assign_reporting_welfare_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        welfare: WelfareFunction,
+        tie_breaking: TieBreaking,
+    ) -> Result<(Assignment, f64), AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        let highest = highest_dissatisfaction(subjects, groups);
+        let scale = 2_f64.powi(NASH_PRECISION);
+        let costs: Vec<Vec<i128>> = subjects
+            .iter()
+            .map(|s| {
+                groups
+                    .iter()
+                    .map(|g| {
+                        let d = s.dissatisfaction(&g.id()) as i128;
+                        match welfare {
+                            WelfareFunction::Utilitarian => d,
+                            WelfareFunction::SumOfSquares => d * d,
+                            // ln(highest + 1) - ln(utility) is not negative. Only groups without seats, where nobody
+                            // is placed, may exceed the highest dissatisfaction.
+                            WelfareFunction::Nash => {
+                                let utility = (highest as i128 - d + 1).max(1) as f64;
+                                (((highest as f64 + 1.0).ln() - utility.ln()) * scale).round()
+                                    as i128
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let ranks: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|s| tie_breaker.ranks(s.id(), groups))
+            .collect();
+        let placement: Vec<usize> = cheapest_placement(
+            subjects.len(),
+            &capacities,
+            |i, j| Some(costs[i][j]),
+            |i, j| ranks[i][j],
+            tie_breaker.seat_costs().as_deref(),
+        )
+        .into_iter()
+        .collect::<Option<_>>()
+        .ok_or_else(|| AssignmentError::Infeasible {
+            reason: "The groups do not offer enough seats for all subjects".to_string(),
+        })?;
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, group_index) in subjects.iter().zip(placement) {
+            group_registries[group_index].subjects.push(subject);
+        }
+        let assignment = super::assign_from_group_registries(group_registries);
+        let value = welfare.value(&assignment, subjects, groups);
+        Ok((assignment, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::DefaultSubject;
+
+    #[test]
+    fn assign_reporting_welfare_trades_off() {
+        let group_ids = [101_u32, 102, 103, 104];
+        // The third subject takes the third group, which raises the highest dissatisfaction to 9 and hence every utility.
+        // Nobody fits into the last group, hence its dissatisfaction of 20 does not count.
+        let subjects = [
+            DefaultSubject::new(
+                1,
+                [(group_ids[0], 0), (group_ids[1], 2), (group_ids[2], 9)]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                20,
+            ),
+            DefaultSubject::new(
+                2,
+                [(group_ids[0], 3), (group_ids[1], 4), (group_ids[2], 9)]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                20,
+            ),
+            DefaultSubject::new(
+                3,
+                [(group_ids[0], 9), (group_ids[1], 9), (group_ids[2], 0)]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                20,
+            ),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+            DefaultGroup::new(group_ids[3], 0),
+        ];
+        let assign = |welfare| {
+            MaximumWelfare::assign_reporting_welfare(&subjects, &groups, welfare).unwrap()
+        };
+        // Dissatisfactions 0, 4 and 0: total 4, squares 16 and utilities 10, 6 and 10
+        let (assignment, total) = assign(WelfareFunction::Utilitarian);
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(4.0, total);
+        let (assignment, product) = assign(WelfareFunction::Nash);
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert!((product - 600_f64.ln()).abs() < 1e-9);
+        // Dissatisfactions 2, 3 and 0: total 5, squares 13 and utilities 8, 7 and 10
+        let (assignment, squares) = assign(WelfareFunction::SumOfSquares);
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(13.0, squares);
+        assert!(assignment.validate(&subjects, &groups).is_empty());
+    }
+}
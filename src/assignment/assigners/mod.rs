@@ -4,39 +4,306 @@
 //!
 //! ## Available assigners:
 //! - [First come first served](FirstComeFirstServed):
-//!  The subjects get assigned to their most preferred available group in turn.
+//!   The subjects get assigned to their most preferred available group in turn.
 //!
 //! - [Propose and reject](ProposeAndReject): First assigns every subject to their first choice regardless of capacity constraints, then the overfull groups handover subjects to the not yet full groups in a manner similar to the Gale-Shapley algorithm.
 //!
+//! - [Immediate acceptance](ImmediateAcceptance): Also known as the Boston mechanism. In every round the subjects not placed yet apply to their next choice, and groups accept applicants by priority for good.
+//!
+//! - [Incremental](Incremental): Re-assigns after subjects or groups have changed, moving as few of the previously placed subjects as possible.
+//!
+//! - [Quota respecting](QuotaRespecting): Keeps the total dissatisfaction low while respecting the groups' [quotas](crate::Quota) on the composition of their members.
+//!
+//! - [Draft](Draft): The subjects pick their most preferred available group in turn, reversing the order after every round. Subjects may [demand](crate::Subject::demand) several seats.
+//!
+//! - [Egalitarian](Egalitarian): Keeps the highest dissatisfaction of any subject as low as possible, and then the total dissatisfaction.
+//!
+//! - [Rank maximal](RankMaximal): Gives as many subjects as possible their first choice, then as many as possible their second choice, and so on.
+//!
+//! - [Maximum welfare](MaximumWelfare): Optimises a configurable [welfare function](WelfareFunction), such as the Nash welfare or the sum of squared dissatisfactions.
+//!
+//! - [Popular](Popular): Finds an assignment that no other assignment beats in a majority vote among the subjects, if there is one.
+//!
+//! ## Ties
+//! All assigners are deterministic. Whenever a subject is equally dissatisfied with several groups, a [tie-breaking policy](TieBreaking) decides between them.
+//!
+//! ## Implementing the trait
+//! Implementations provide [assign_with_tie_breaking](Assigner::assign_with_tie_breaking), from which [assign](Assigner::assign) is derived.
+//! This breaks implementations written against earlier versions of the trait, which provided `assign` instead:
+//! they need to rename it to `assign_with_tie_breaking` and take (or ignore) the additional tie-breaking policy.
+//!
 
+mod draft;
+mod egalitarian;
 mod first_come_first_served;
+mod immediate_acceptance;
+mod incremental;
+mod maximum_welfare;
+mod popular;
 mod propose_and_reject;
+mod quota_respecting;
+mod rank_maximal;
 use super::{group_management::*, Assignment};
-use crate::assignment::errors::TotalCapacityError;
+pub use crate::assignment::comparison::Move;
+use crate::assignment::errors::{self, AssignmentError};
+use crate::assignment::tie_breaking::TieBreaker;
+pub use crate::assignment::tie_breaking::TieBreaking;
 use crate::groups::Group;
 use crate::subjects::Subject;
+pub use draft::{Draft, DraftOrder, MultiAssignment};
+pub use egalitarian::Egalitarian;
 pub use first_come_first_served::FirstComeFirstServed;
+pub use immediate_acceptance::ImmediateAcceptance;
+pub use incremental::{Incremental, MovePolicy, Reassignment};
+pub use maximum_welfare::{MaximumWelfare, WelfareFunction};
+pub use popular::Popular;
 pub use propose_and_reject::ProposeAndReject;
+pub use quota_respecting::QuotaRespecting;
+pub use rank_maximal::{RankMaximal, RankSignature};
 
 /// Trait enabling group assignments.
 pub trait Assigner {
     /// Assign the given subjects to the given groups
-    /// When the total capacity of the groups is sufficient a pair of maps (subject ids -> group ids, group ids -> subject ids) is returned
+    /// When the ids are unique and the total capacity of the groups is sufficient a pair of maps (subject ids -> group ids, group ids -> subject ids) is returned
+    ///
+    /// Ties are broken according to the [default policy](TieBreaking::GroupOrder).
     fn assign<S: Subject, G: Group>(
         subjects: &[S],
         groups: &[G],
-    ) -> Result<Assignment, TotalCapacityError>;
+    ) -> Result<Assignment, AssignmentError> {
+        Self::assign_with_tie_breaking(subjects, groups, TieBreaking::default())
+    }
 
-    /// This method must be called by assign and in the case of an error it must be forwarded.
+    /// Assign the given subjects to the given groups, deciding between groups a subject is equally dissatisfied with according to the given policy.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed, TieBreaking};
+    /// use std::collections::HashMap;
+    /// let indifferent: HashMap<u32, u32> = [(101, 0), (102, 0)].iter().cloned().collect();
+    /// let subjects = [
+    ///     DefaultSubject::new(1, indifferent.clone(), 0),
+    ///     DefaultSubject::new(2, indifferent, 0),
+    /// ];
+    /// let groups = [DefaultGroup::new(102, 2), DefaultGroup::new(101, 2)];
+    /// let assignment =
+    ///     FirstComeFirstServed::assign_with_tie_breaking(&subjects, &groups, TieBreaking::GroupId).unwrap();
+    /// assert_eq!(Some(&vec![1, 2]), assignment.group_id_to_subjects_ids(&101));
+    /// let assignment =
+    ///     FirstComeFirstServed::assign_with_tie_breaking(&subjects, &groups, TieBreaking::LeastFilled).unwrap();
+    /// assert_eq!(Some(&vec![1]), assignment.group_id_to_subjects_ids(&102));
+    /// ```
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError>;
+
+    /// This method must be called by assign_with_tie_breaking before anything else and in the case of an error it must be forwarded.
+    fn unique_ids<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+    ) -> Result<(), AssignmentError> {
+        errors::unique_ids(
+            subjects.iter().map(|x| x.id()),
+            groups.iter().map(|x| x.id()),
+        )
+    }
+
+    /// This method must be called by assign_with_tie_breaking and in the case of an error it must be forwarded.
     fn sufficient_capacity<S: Subject, G: Group>(
         subjects: &[S],
         groups: &[G],
-    ) -> Result<(), TotalCapacityError> {
-        let capacity: u32 = groups.iter().map(|x| x.capacity()).sum();
-        if capacity >= (subjects.len() as u32) {
-            Ok(())
-        } else {
-            Err(TotalCapacityError {})
+    ) -> Result<(), AssignmentError> {
+        errors::sufficient_capacity(subjects.len(), groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn assign_duplicate_ids() {
+        let subjects = [
+            TestSubject::new(1, vec![]),
+            TestSubject::new(2, vec![]),
+            TestSubject::new(1, vec![]),
+        ];
+        let groups = [
+            DefaultGroup::new(101, 2),
+            DefaultGroup::new(102, 2),
+            DefaultGroup::new(101, 2),
+            DefaultGroup::new(101, 2),
+        ];
+        let check = |result: Result<Assignment, AssignmentError>| match result {
+            Err(error) => assert_eq!(
+                AssignmentError::DuplicateIds {
+                    subject_ids: vec![1],
+                    group_ids: vec![101]
+                },
+                error
+            ),
+            Ok(_) => panic!("duplicate ids were not detected"),
+        };
+        check(FirstComeFirstServed::assign(&subjects, &groups));
+        check(Incremental::assign(&subjects, &groups));
+        check(QuotaRespecting::assign(&subjects, &groups));
+        check(Egalitarian::assign(&subjects, &groups));
+        check(RankMaximal::assign(&subjects, &groups));
+        check(Popular::assign(&subjects, &groups));
+        check(ImmediateAcceptance::assign(&subjects, &groups));
+        check(Draft::assign(&subjects, &groups));
+        check(MaximumWelfare::assign(&subjects, &groups));
+    }
+
+    #[test]
+    fn sufficient_capacity_large_capacities() {
+        let subjects: Vec<TestSubject> = (0..3).map(|id| TestSubject::new(id, vec![])).collect();
+        let groups = [
+            DefaultGroup::new(101, u32::MAX - 1),
+            DefaultGroup::new(102, u32::MAX - 1),
+        ];
+        assert!(FirstComeFirstServed::sufficient_capacity(&subjects, &groups).is_ok());
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+        assert_eq!(
+            Err(AssignmentError::InsufficientCapacity {
+                required: 3,
+                available: 2
+            }),
+            FirstComeFirstServed::sufficient_capacity(&subjects, &groups)
+        );
+    }
+
+    #[test]
+    fn assign_unlimited_capacity() {
+        let subjects: Vec<TestSubject> = (0..5)
+            .map(|id| TestSubject::new(id, vec![102, 101]))
+            .collect();
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::unlimited(102)];
+        for assignment in [
+            FirstComeFirstServed::assign(&subjects, &groups).unwrap(),
+            ProposeAndReject::assign(&subjects, &groups).unwrap(),
+            Incremental::assign(&subjects, &groups).unwrap(),
+            QuotaRespecting::assign(&subjects, &groups).unwrap(),
+            Egalitarian::assign(&subjects, &groups).unwrap(),
+            RankMaximal::assign(&subjects, &groups).unwrap(),
+            Popular::assign(&subjects, &groups).unwrap(),
+            ImmediateAcceptance::assign(&subjects, &groups).unwrap(),
+            Draft::assign(&subjects, &groups).unwrap(),
+            MaximumWelfare::assign(&subjects, &groups).unwrap(),
+        ] {
+            let mut members = assignment.group_id_to_subjects_ids(&102).unwrap().clone();
+            members.sort_unstable();
+            assert_eq!(vec![0, 1, 2, 3, 4], members);
+        }
+    }
+
+    #[test]
+    fn assign_overridden_unlimited_capacity() {
+        // A group declaring unlimited capacity without returning UNLIMITED_CAPACITY
+        struct OpenGroup {}
+        impl Group for OpenGroup {
+            fn id(&self) -> u32 {
+                101
+            }
+            fn capacity(&self) -> u32 {
+                1
+            }
+            fn has_unlimited_capacity(&self) -> bool {
+                true
+            }
+        }
+        let subjects: Vec<TestSubject> = (0..3).map(|id| TestSubject::new(id, vec![101])).collect();
+        let groups = [OpenGroup {}];
+        for assignment in [
+            FirstComeFirstServed::assign(&subjects, &groups).unwrap(),
+            ProposeAndReject::assign(&subjects, &groups).unwrap(),
+            Incremental::assign(&subjects, &groups).unwrap(),
+            QuotaRespecting::assign(&subjects, &groups).unwrap(),
+            Egalitarian::assign(&subjects, &groups).unwrap(),
+            RankMaximal::assign(&subjects, &groups).unwrap(),
+            Popular::assign(&subjects, &groups).unwrap(),
+            ImmediateAcceptance::assign(&subjects, &groups).unwrap(),
+            Draft::assign(&subjects, &groups).unwrap(),
+            MaximumWelfare::assign(&subjects, &groups).unwrap(),
+        ] {
+            assert_eq!(3, assignment.group_id_to_subjects_ids(&101).unwrap().len());
+            assert!(assignment.validate(&subjects, &groups).is_empty());
+        }
+    }
+
+    #[test]
+    fn assign_reserved_seats() {
+        let subjects: Vec<TestSubject> = (0..2).map(|id| TestSubject::new(id, vec![101])).collect();
+        let groups = [DefaultGroup::new(101, 2)
+            .with_reserved_seats(vec![crate::groups::SeatReservation::new(1, 1, 1)])];
+        // Assigners that honour reserved seats release the unused one
+        for assignment in [
+            FirstComeFirstServed::assign(&subjects, &groups).unwrap(),
+            ImmediateAcceptance::assign(&subjects, &groups).unwrap(),
+            Draft::assign(&subjects, &groups).unwrap(),
+            ProposeAndReject::assign(&subjects, &groups).unwrap(),
+        ] {
+            assert_eq!(2, assignment.group_id_to_subjects_ids(&101).unwrap().len());
+        }
+        // The others refuse to ignore them
+        let check = |result: Result<Assignment, AssignmentError>| {
+            assert!(matches!(result, Err(AssignmentError::InvalidInput { .. })))
+        };
+        check(Incremental::assign(&subjects, &groups));
+        check(QuotaRespecting::assign(&subjects, &groups));
+        check(Egalitarian::assign(&subjects, &groups));
+        check(RankMaximal::assign(&subjects, &groups));
+        check(Popular::assign(&subjects, &groups));
+        check(MaximumWelfare::assign(&subjects, &groups));
+    }
+
+    #[test]
+    fn assign_with_tie_breaking() {
+        // Every subject is equally satisfied with both groups
+        let subjects: Vec<TestSubject> = (0..4).map(|id| TestSubject::new(id, vec![])).collect();
+        let groups = [DefaultGroup::new(102, 4), DefaultGroup::new(101, 4)];
+        fn sizes<A: Assigner>(
+            subjects: &[TestSubject],
+            groups: &[DefaultGroup],
+            tie_breaking: TieBreaking,
+        ) -> (usize, usize) {
+            let assignment = A::assign_with_tie_breaking(subjects, groups, tie_breaking).unwrap();
+            let size = |id: u32| {
+                assignment
+                    .group_id_to_subjects_ids(&id)
+                    .map_or(0, |x| x.len())
+            };
+            (size(102), size(101))
+        }
+        fn check<A: Assigner>(subjects: &[TestSubject], groups: &[DefaultGroup]) {
+            assert_eq!(
+                (4, 0),
+                sizes::<A>(subjects, groups, TieBreaking::GroupOrder)
+            );
+            assert_eq!((0, 4), sizes::<A>(subjects, groups, TieBreaking::GroupId));
+            assert_eq!(
+                (2, 2),
+                sizes::<A>(subjects, groups, TieBreaking::LeastFilled)
+            );
+            let seeded = A::assign_with_tie_breaking(subjects, groups, TieBreaking::Seeded(7));
+            assert_eq!(
+                seeded.unwrap(),
+                A::assign_with_tie_breaking(subjects, groups, TieBreaking::Seeded(7)).unwrap()
+            );
         }
+        check::<FirstComeFirstServed>(&subjects, &groups);
+        check::<ProposeAndReject>(&subjects, &groups);
+        check::<Incremental>(&subjects, &groups);
+        check::<QuotaRespecting>(&subjects, &groups);
+        check::<Egalitarian>(&subjects, &groups);
+        check::<RankMaximal>(&subjects, &groups);
+        check::<Popular>(&subjects, &groups);
+        check::<ImmediateAcceptance>(&subjects, &groups);
+        check::<Draft>(&subjects, &groups);
+        check::<MaximumWelfare>(&subjects, &groups);
     }
 }
@@ -0,0 +1,401 @@
+//! # Popular
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) producing popular assignments.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::min_cost_flow::{cheapest_placement, LexicographicCost, MinCostFlow};
+use crate::groups::effective_capacity;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+use std::collections::VecDeque;
+
+/// Assigns such that no other assignment would win a majority vote among the subjects, where every subject votes for the assignment placing it in the group
+/// it is less dissatisfied with (and abstains if it is equally dissatisfied with both groups).
+///
+///
+/// Popular assignments need not exist, in which case an [Infeasible](AssignmentError::Infeasible) error is returned.
+///
+/// The assignment is found with the algorithm of Abraham, Irving, Kavitha and Mehlhorn for preferences with ties, where every seat of a group is treated as a
+/// separate house: First as many subjects as possible are placed in groups they are least dissatisfied with (their first choices). The way this placement can
+/// be altered determines, for every subject, its second choices. An assignment is popular if and only if it keeps the number of subjects placed in a first
+/// choice as high as possible and places every subject in a first or second choice.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Assigner, Popular};
+/// use group_assignment::errors::AssignmentError;
+/// let ranked = |id| DefaultSubject::new(id, [(101, 0), (102, 1), (103, 2)].iter().cloned().collect(), 3);
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1), DefaultGroup::new(103, 1)];
+/// // One subject gets its first choice, the other one its second choice
+/// let subjects = [ranked(1), ranked(2)];
+/// let assignment = Popular::assign(&subjects, &groups).unwrap();
+/// let placed: Vec<u32> = subjects.iter().map(|s| *assignment.subject_to_group_id(s).unwrap()).collect();
+/// assert!(placed.contains(&101) && placed.contains(&102));
+/// // Three subjects with the same preferences can not be assigned popularly:
+/// // whoever gets the last group would vote for a rotation together with whoever gets the second group
+/// let subjects = [ranked(1), ranked(2), ranked(3)];
+/// assert!(matches!(Popular::assign(&subjects, &groups), Err(AssignmentError::Infeasible { .. })));
+/// ```
+pub struct Popular {}
+
+// The classes of the Gallai-Edmonds decomposition of the first choice graph with respect to a maximum matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Even,
+    Odd,
+    Unreachable,
+}
+
+impl Assigner for Popular {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        let (number_of_subjects, number_of_groups) = (subjects.len(), groups.len());
+        let dissatisfaction: Vec<Vec<u32>> = subjects
+            .iter()
+            .map(|s| groups.iter().map(|g| s.dissatisfaction(&g.id())).collect())
+            .collect();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let seats: Vec<usize> = capacities
+            .iter()
+            .map(|c| (*c).min(number_of_subjects))
+            .collect();
+        let first_choices: Vec<Vec<usize>> = dissatisfaction
+            .iter()
+            .map(|d| {
+                let lowest = d.iter().min().copied().unwrap_or(0);
+                (0..number_of_groups).filter(|j| d[*j] == lowest).collect()
+            })
+            .collect();
+        let mut first_choice_of: Vec<Vec<usize>> = vec![Vec::new(); number_of_groups];
+        for (i, choices) in first_choices.iter().enumerate() {
+            for j in choices {
+                first_choice_of[*j].push(i);
+            }
+        }
+
+        // A maximum matching of the first choice graph and its decomposition
+        let matched = cheapest_placement(
+            number_of_subjects,
+            &capacities,
+            |i, j| first_choices[i].contains(&j).then_some(0),
+            |_i, _j| 0,
+            None,
+        );
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); number_of_groups];
+        for (i, j) in matched.iter().enumerate() {
+            if let Some(j) = j {
+                members[*j].push(i);
+            }
+        }
+        let mut classes = vec![Class::Unreachable; number_of_subjects];
+        // Subjects are even if an alternating path leads to them from an unmatched subject
+        let mut queue: VecDeque<usize> = (0..number_of_subjects)
+            .filter(|i| matched[*i].is_none())
+            .collect();
+        for i in queue.iter() {
+            classes[*i] = Class::Even;
+        }
+        while let Some(i) = queue.pop_front() {
+            for j in first_choices[i].iter() {
+                for b in members[*j].iter() {
+                    if *b != i && classes[*b] == Class::Unreachable {
+                        classes[*b] = Class::Even;
+                        queue.push_back(*b);
+                    }
+                }
+            }
+        }
+        // Subjects are odd if an alternating path leads to them from a free seat. The seat of an odd subject is even,
+        // so it leads on to every other subject with the same first choice.
+        let mut queue: VecDeque<(usize, Option<usize>)> = (0..number_of_groups)
+            .filter(|j| members[*j].len() < seats[*j])
+            .map(|j| (j, None))
+            .collect();
+        while let Some((j, owner)) = queue.pop_front() {
+            for a in first_choice_of[j].iter() {
+                if Some(*a) != owner && classes[*a] == Class::Unreachable {
+                    classes[*a] = Class::Odd;
+                    if let Some(k) = matched[*a] {
+                        queue.push_back((k, Some(*a)));
+                    }
+                }
+            }
+        }
+        // Seats have the class opposite to their occupant's, free seats are even
+        let seats_of_class = |j: usize, class: Class| -> usize {
+            let occupied_by = |occupant_class: Class| {
+                members[j]
+                    .iter()
+                    .filter(|i| classes[**i] == occupant_class)
+                    .count()
+            };
+            match class {
+                Class::Even => seats[j] - members[j].len() + occupied_by(Class::Odd),
+                Class::Odd => occupied_by(Class::Even),
+                Class::Unreachable => occupied_by(Class::Unreachable),
+            }
+        };
+        let even_seats: Vec<usize> = (0..number_of_groups)
+            .map(|j| seats_of_class(j, Class::Even))
+            .collect();
+        // The second choices of a subject are the groups with even seats it is least dissatisfied with
+        let second_choices: Vec<Vec<usize>> = dissatisfaction
+            .iter()
+            .map(|d| {
+                let lowest = (0..number_of_groups)
+                    .filter(|j| even_seats[*j] > 0)
+                    .map(|j| d[j])
+                    .min();
+                (0..number_of_groups)
+                    .filter(|j| even_seats[*j] > 0 && Some(d[*j]) == lowest)
+                    .collect()
+            })
+            .collect();
+
+        // Subjects are placed in seats of their first and second choices, where first choices are restricted to seats of the classes that are compatible
+        // with the subject's class. Occupying all odd and unreachable seats ensures that as many subjects as possible get a first choice.
+        // Costs are compared lexicographically: odd and unreachable seats first, then the costs of the tie-breaking policy.
+        let class_node = |j: usize, class: Class| {
+            number_of_subjects
+                + 3 * j
+                + match class {
+                    Class::Even => 0,
+                    Class::Odd => 1,
+                    Class::Unreachable => 2,
+                }
+        };
+        let group_node = |j: usize| number_of_subjects + 3 * number_of_groups + j;
+        let source = number_of_subjects + 4 * number_of_groups;
+        let sink = source + 1;
+        let mut network: MinCostFlow<LexicographicCost> = MinCostFlow::new(sink + 1);
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let mut subject_edges: Vec<Vec<(usize, usize)>> = Vec::with_capacity(number_of_subjects);
+        for (i, subject) in subjects.iter().enumerate() {
+            network.add_edge(source, i, 1, LexicographicCost::default());
+            let tie_ranks = tie_breaker.ranks(subject.id(), groups);
+            let first_choice_class = match classes[i] {
+                Class::Even => Class::Odd,
+                Class::Odd => Class::Even,
+                Class::Unreachable => Class::Unreachable,
+            };
+            let mut edges: Vec<(usize, Class)> = first_choices[i]
+                .iter()
+                .map(|j| (*j, first_choice_class))
+                .collect();
+            edges.extend(
+                second_choices[i]
+                    .iter()
+                    .filter(|j| first_choice_class != Class::Even || !first_choices[i].contains(j))
+                    .map(|j| (*j, Class::Even)),
+            );
+            subject_edges.push(
+                edges
+                    .into_iter()
+                    .map(|(j, class)| {
+                        let edge = network.add_edge(
+                            i,
+                            class_node(j, class),
+                            1,
+                            LexicographicCost(vec![0, 0, tie_ranks[j]]),
+                        );
+                        (j, edge)
+                    })
+                    .collect(),
+            );
+        }
+        let seat_costs = tie_breaker.seat_costs();
+        let mut required_seats = 0;
+        for (j, seats) in seats.iter().enumerate() {
+            for class in [Class::Even, Class::Odd, Class::Unreachable] {
+                let count = seats_of_class(j, class);
+                let cost = if class == Class::Even { 0 } else { -1 };
+                if class != Class::Even {
+                    required_seats += count;
+                }
+                network.add_edge(
+                    class_node(j, class),
+                    group_node(j),
+                    count as i64,
+                    LexicographicCost(vec![cost]),
+                );
+            }
+            match seat_costs.as_deref() {
+                Some(seat_cost) => {
+                    for k in 0..*seats {
+                        network.add_edge(
+                            group_node(j),
+                            sink,
+                            1,
+                            LexicographicCost(vec![0, seat_cost(j, k)]),
+                        );
+                    }
+                }
+                None => {
+                    network.add_edge(
+                        group_node(j),
+                        sink,
+                        *seats as i64,
+                        LexicographicCost::default(),
+                    );
+                }
+            }
+        }
+        let (placed, cost) = network.run(source, sink, number_of_subjects as i64);
+        if placed < number_of_subjects as i64
+            || cost.0.first().copied().unwrap_or(0) > -(required_seats as i64)
+        {
+            return Err(AssignmentError::Infeasible {
+                reason: "Every assignment is less popular than some other assignment".to_string(),
+            });
+        }
+
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, edges) in subjects.iter().zip(subject_edges.iter()) {
+            if let Some((j, _)) = edges.iter().find(|(_, edge)| network.flow(*edge) > 0) {
+                group_registries[*j].subjects.push(subject);
+            }
+        }
+        Ok(super::assign_from_group_registries(group_registries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    // Counts the subjects preferring the first assignment and the subjects preferring the second one
+    fn votes(subjects: &[TestSubject], first: &Assignment, second: &Assignment) -> (usize, usize) {
+        let dissatisfaction = |s: &TestSubject, assignment: &Assignment| {
+            s.dissatisfaction(assignment.subject_to_group_id(s).unwrap())
+        };
+        let first_votes = subjects
+            .iter()
+            .filter(|s| dissatisfaction(s, first) < dissatisfaction(s, second))
+            .count();
+        let second_votes = subjects
+            .iter()
+            .filter(|s| dissatisfaction(s, second) < dissatisfaction(s, first))
+            .count();
+        (first_votes, second_votes)
+    }
+
+    // Every assignment of the subjects to the groups (with capacity one each), given as a group index per subject
+    fn all_assignments(number_of_subjects: usize, number_of_groups: usize) -> Vec<Vec<usize>> {
+        if number_of_subjects == 0 {
+            return vec![Vec::new()];
+        }
+        let mut result = Vec::new();
+        for partial in all_assignments(number_of_subjects - 1, number_of_groups) {
+            for j in (0..number_of_groups).filter(|j| !partial.contains(j)) {
+                let mut extended = partial.clone();
+                extended.push(j);
+                result.push(extended);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn assign_popular_against_brute_force() {
+        let group_ids = [101_u32, 102, 103, 104];
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 1))
+            .collect();
+        let preferences: [&[usize]; 5] = [
+            &[0, 1, 2, 3],
+            &[0, 2, 1, 3],
+            &[1, 0],
+            &[2, 3, 1, 0],
+            &[0, 1],
+        ];
+        let mut outcomes = (0, 0);
+        // Try several instances made of four of the preference lists
+        for left_out in 0..preferences.len() {
+            let subjects: Vec<TestSubject> = (0..preferences.len())
+                .filter(|k| *k != left_out)
+                .enumerate()
+                .map(|(id, k)| {
+                    TestSubject::new(
+                        id as u32,
+                        preferences[k].iter().map(|j| group_ids[*j]).collect(),
+                    )
+                })
+                .collect();
+            let others: Vec<Assignment> = all_assignments(subjects.len(), groups.len())
+                .into_iter()
+                .map(|placement| {
+                    let pairs: Vec<(u32, u32)> = placement
+                        .iter()
+                        .enumerate()
+                        .map(|(i, j)| (subjects[i].id(), group_ids[*j]))
+                        .collect();
+                    Assignment::from((
+                        pairs.iter().cloned().collect(),
+                        pairs.iter().map(|(s, g)| (*g, vec![*s])).collect(),
+                    ))
+                })
+                .collect();
+            let exists = others.iter().any(|candidate| {
+                others.iter().all(|other| {
+                    let (for_candidate, for_other) = votes(&subjects, candidate, other);
+                    for_candidate >= for_other
+                })
+            });
+            match Popular::assign(&subjects, &groups) {
+                Ok(assignment) => {
+                    outcomes.0 += 1;
+                    assert!(others.iter().all(|other| {
+                        let (for_assignment, for_other) = votes(&subjects, &assignment, other);
+                        for_assignment >= for_other
+                    }));
+                }
+                Err(AssignmentError::Infeasible { .. }) => {
+                    outcomes.1 += 1;
+                    assert!(!exists)
+                }
+                Err(error) => panic!("unexpected error {}", error),
+            }
+        }
+        // Popular assignments exist for some but not all of the instances
+        assert!(outcomes.0 > 0 && outcomes.1 > 0, "{:?}", outcomes);
+    }
+
+    #[test]
+    fn assign_popular_with_capacities() {
+        let group_ids = [101_u32, 102];
+        // Everyone wants the first group, which has room for two
+        let subjects: Vec<TestSubject> = (0..4)
+            .map(|id| TestSubject::new(id, vec![group_ids[0], group_ids[1]]))
+            .collect();
+        let groups = [
+            DefaultGroup::new(group_ids[0], 2),
+            DefaultGroup::new(group_ids[1], 2),
+        ];
+        let assignment = Popular::assign(&subjects, &groups).unwrap();
+        assert_eq!(
+            2,
+            assignment
+                .group_id_to_subjects_ids(&group_ids[0])
+                .unwrap()
+                .len()
+        );
+        assert!(assignment.validate(&subjects, &groups).is_empty());
+    }
+}
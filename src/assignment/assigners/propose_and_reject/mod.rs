@@ -1,14 +1,15 @@
 //! # Propose and reject
 //! This module provides an [assigner](crate::assignment::assigners::Assigner) inspired by the Gale-Shapley algorithm (also known as the propose-and-reject algorithm).
 //!
-use std::collections::HashSet;
 use std::iter::FromIterator;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
 use super::Assigner;
+use super::AssignmentError;
 use super::GroupRegistry;
-use super::TotalCapacityError;
+use super::GrowingGroupRegistry;
+use super::{TieBreaker, TieBreaking};
 mod proposals;
 use crate::subjects::Subject;
 use crate::{assignment::Assignment, groups::Group};
@@ -25,29 +26,37 @@ use proposals::ProposalHandlingGroupRegistry;
 /// it is proposed to accept. Otherwise the group can accept the new subject, but if the proposed group is already at full capacity, it must first discard its most dissatisfied member
 /// and return it to the group according to the discarded member's first choice regardless of capacity constraints.
 /// This propose and reject/accept process continues until there are no more overfull groups.
+///
+/// A group with [reserved seats](crate::Group::reserved_seats) is also overfull when some of its members can neither take a reserved seat they are eligible for
+/// nor a general seat, and only rejects members whose departure makes room. Once no overfull group can transfer a member, the unused reserved seats are released.
 pub struct ProposeAndReject {}
 
 impl Assigner for ProposeAndReject {
-    fn assign<S: Subject, G: Group>(
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
         subjects: &[S],
         groups: &[G],
-    ) -> Result<Assignment, TotalCapacityError> {
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
         Self::sufficient_capacity(subjects, groups)?;
-        let group_registries = first_step(subjects, groups);
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let group_registries = first_step(subjects, groups, &tie_breaker);
         // Partition the registries into those whose corresponding groups will be overfull, full, and available respectively
-        let mut registries_partition = RegristriesPartition::from_first_step(group_registries);
-        while registries_partition.overfull.len() > 0 {
+        let mut registries_partition = RegristriesPartition::from_registries(group_registries);
+        while !registries_partition.overfull.is_empty() {
+            if !registries_partition.transferral_possible() {
+                // The remaining seats are all reserved for subjects that are not in the overfull groups
+                registries_partition = registries_partition.release_reserved_seats();
+            }
             // The following is a workaround until destructuring assignments stabilizes: See https://github.com/rust-lang/rust/issues/71126
-            registries_partition = proposal_round(registries_partition);
+            registries_partition = proposal_round(registries_partition, &tie_breaker);
         }
         let (available, bystanders) = (
             registries_partition.available,
             registries_partition.bystanders,
         );
-        let resolved_registries: Vec<ProposalHandlingGroupRegistry<S, G>> = available
-            .into_iter()
-            .chain(bystanders.into_iter())
-            .collect();
+        let resolved_registries: Vec<ProposalHandlingGroupRegistry<S, G>> =
+            available.into_iter().chain(bystanders).collect();
 
         Ok(super::assign_from_group_registries(resolved_registries))
     }
@@ -129,90 +138,84 @@ impl<'a, S: Subject, G: Group> RegristriesPartition<'a, S, G> {
             available,
         }
     }
-    fn from_first_step(group_registries: ProposalHandlingGroupRegistries<'a, S, G>) -> Self {
-        let (full, available): (
+    fn from_registries(group_registries: ProposalHandlingGroupRegistries<'a, S, G>) -> Self {
+        // A group may be overfull without being full when too few of its members are eligible for its reserved seats
+        let (overfull, rest): (
             ProposalHandlingGroupRegistries<'a, S, G>,
             ProposalHandlingGroupRegistries<'a, S, G>,
-        ) = group_registries.into_iter().partition(|x| x.full());
-        let (overfull, bystanders) = full.into_iter().partition(|x| x.overfull());
+        ) = group_registries.into_iter().partition(|x| x.overfull());
+        let (bystanders, available) = rest.into_iter().partition(|x| x.full());
         Self {
             overfull,
             bystanders,
             available,
         }
     }
+
+    // Whether some overfull group can transfer one of its members to an available group
+    fn transferral_possible(&self) -> bool {
+        self.overfull.iter().any(|overfull_group| {
+            self.available
+                .iter()
+                .any(|x| overfull_group.propose_transferral(x).is_some())
+        })
+    }
+
+    // Releases the reserved seats of every group and partitions the registries anew
+    fn release_reserved_seats(self) -> Self {
+        let mut group_registries: ProposalHandlingGroupRegistries<'a, S, G> = self
+            .overfull
+            .into_iter()
+            .chain(self.bystanders)
+            .chain(self.available)
+            .collect();
+        for group_registry in group_registries.iter_mut() {
+            group_registry.release_reserved_seats();
+        }
+        Self::from_registries(group_registries)
+    }
 }
 
 // The first step of the propose and reject algorithm.
 // Create a group manager for each group and register every subject to a group manager corresponding to the subjects preferred choice.
-// In the most general case where a subject might have more than one group with dissatisfaction rating 0, the first one appearing in the groups vector is chosen.
+// In the most general case where a subject might have more than one group with dissatisfaction rating 0, the tie breaker decides.
+// The subjects are processed in the given order so that the outcome is reproducible.
 fn first_step<'a, S: Subject, G: Group>(
     subjects: &'a [S],
     groups: &'a [G],
+    tie_breaker: &TieBreaker,
 ) -> ProposalHandlingGroupRegistries<'a, S, G> {
-    let mut group_registries: Vec<_> = Vec::new();
-    let mut unprocessed_subjects_indices: HashSet<usize> =
-        (0..subjects.len()).into_iter().collect();
-    for group in groups {
-        let id = group.id();
-        let subjects_processed_this_iteration: Vec<&'a S> = Vec::new();
-        let indices_processed_this_iteration: HashSet<usize> = HashSet::new();
-        let (indices_processed_this_iteration, subjects_processed_this_iteration) =
-            unprocessed_subjects_indices
-                .iter()
-                .map(|i| (i, subjects.get(*i).unwrap()))
-                .filter(|(_i, x)| x.dissatisfaction(&id) == 0)
-                .fold(
-                    (
-                        indices_processed_this_iteration,
-                        subjects_processed_this_iteration,
-                    ),
-                    |mut acc, (i, x)| {
-                        acc.0.insert(*i);
-                        acc.1.push(x);
-                        acc
-                    },
-                );
-        group_registries.push(ProposalHandlingGroupRegistry::new_without_dissatisfaction(
-            group,
-            subjects_processed_this_iteration,
-        ));
-        unprocessed_subjects_indices = unprocessed_subjects_indices
-            .difference(&indices_processed_this_iteration)
-            .copied()
-            .collect();
-    }
-    if !unprocessed_subjects_indices.is_empty() {
-        // This means that there were subjects that gave every group a dissatisfaction rating more than 0
-        // We pass these to a group manager by the first come first served principle
-        group_registries = handle_subjects_without_first_choice_first_step(
-            subjects,
-            unprocessed_subjects_indices,
-            group_registries,
-        );
+    let mut members: Vec<Vec<&'a S>> = vec![Vec::new(); groups.len()];
+    let mut unprocessed_subjects: Vec<&'a S> = Vec::new();
+    for subject in subjects {
+        match (0..groups.len())
+            .filter(|j| subject.dissatisfaction(&groups[*j].id()) == 0)
+            .min_by_key(|j| tie_breaker.key(subject.id(), groups[*j].id(), members[*j].len()))
+        {
+            Some(j) => members[j].push(subject),
+            None => unprocessed_subjects.push(subject),
+        }
     }
-    ProposalHandlingGroupRegistries { group_registries }
-}
-
-fn handle_subjects_without_first_choice_first_step<'a, S: Subject, G: Group>(
-    subjects: &'a [S],
-    unprocessed_subject_indices: HashSet<usize>,
-    mut group_registries: Vec<ProposalHandlingGroupRegistry<'a, S, G>>,
-) -> Vec<ProposalHandlingGroupRegistry<'a, S, G>> {
-    for subject in unprocessed_subject_indices
+    let mut group_registries: Vec<_> = groups
         .iter()
-        .map(|i| subjects.get(*i).unwrap())
-    {
+        .zip(members)
+        .map(|(group, members)| {
+            ProposalHandlingGroupRegistry::new_without_dissatisfaction(group, members)
+        })
+        .collect();
+    // These subjects gave every group a dissatisfaction rating more than 0
+    // We pass these to a group manager by the first come first served principle
+    for subject in unprocessed_subjects {
         group_registries =
-            super::subject_to_best_available_group_registry(subject, group_registries);
+            super::subject_to_best_available_group_registry(subject, group_registries, tie_breaker);
     }
-
-    group_registries
+    ProposalHandlingGroupRegistries { group_registries }
 }
 
-fn proposal_round<S: Subject, G: Group>(
-    registries_partition: RegristriesPartition<S, G>,
-) -> RegristriesPartition<S, G> {
+fn proposal_round<'a, S: Subject, G: Group>(
+    registries_partition: RegristriesPartition<'a, S, G>,
+    tie_breaker: &TieBreaker,
+) -> RegristriesPartition<'a, S, G> {
     let mut subjects_for_reprocessing: Vec<&S> = Vec::new();
     let (mut overfull, bystanders, mut available) = (
         registries_partition.overfull,
@@ -220,14 +223,17 @@ fn proposal_round<S: Subject, G: Group>(
         registries_partition.available,
     );
     for overfull_group in overfull.iter_mut() {
-        let (transfer_destination_key, offer) = available
+        // Earlier transferrals in this round may have taken the seats this group's members could have had
+        let Some((transfer_destination_key, offer)) = available
             .iter()
             .enumerate()
             .map(|(i, x)| (i, overfull_group.propose_transferral(x)))
             .filter(|(_i, x)| x.is_some())
             .min_by(|(_i, x), (_j, y)| x.cmp(y))
             .map(|(i, x)| (i, x.unwrap()))
-            .unwrap();
+        else {
+            continue;
+        };
 
         if let Some(potentially_replaced_subject) =
             overfull_group.transfer(available.get_mut(transfer_destination_key).unwrap(), offer)
@@ -238,6 +244,7 @@ fn proposal_round<S: Subject, G: Group>(
     group_registries_for_next_proposal_round(
         RegristriesPartition::new(overfull, bystanders, available),
         subjects_for_reprocessing,
+        tie_breaker,
     )
 }
 
@@ -246,6 +253,7 @@ fn proposal_round<S: Subject, G: Group>(
 fn group_registries_for_next_proposal_round<'a, S: Subject, G: Group>(
     registries_partition: RegristriesPartition<'a, S, G>,
     subjects_for_reprocessing: Vec<&'a S>,
+    tie_breaker: &TieBreaker,
 ) -> RegristriesPartition<'a, S, G> {
     let (overfull, bystanders, available) = (
         registries_partition.overfull,
@@ -253,10 +261,10 @@ fn group_registries_for_next_proposal_round<'a, S: Subject, G: Group>(
         registries_partition.available,
     );
     let mut registries_for_update: ProposalHandlingGroupRegistries<'a, S, G> =
-        overfull.into_iter().chain(bystanders.into_iter()).collect();
+        overfull.into_iter().chain(bystanders).collect();
     for subject in subjects_for_reprocessing {
         registries_for_update =
-            subject_to_most_desired_group_registry(registries_for_update, subject);
+            subject_to_most_desired_group_registry(registries_for_update, subject, tie_breaker);
     }
     let (overfull, bystanders): (
         ProposalHandlingGroupRegistries<'a, S, G>,
@@ -270,11 +278,13 @@ fn group_registries_for_next_proposal_round<'a, S: Subject, G: Group>(
 fn subject_to_most_desired_group_registry<'a, S: Subject, G: Group>(
     mut proposal_registries: ProposalHandlingGroupRegistries<'a, S, G>,
     subject: &'a S,
+    tie_breaker: &TieBreaker,
 ) -> ProposalHandlingGroupRegistries<'a, S, G> {
-    if let Some(x) = proposal_registries.iter_mut().min_by(|x, y| {
-        subject
-            .dissatisfaction(&x.id())
-            .cmp(&subject.dissatisfaction(&y.id()))
+    if let Some(x) = proposal_registries.iter_mut().min_by_key(|x| {
+        (
+            subject.dissatisfaction(&x.id()),
+            tie_breaker.key(subject.id(), x.id(), x.number_of_members()),
+        )
     }) {
         x.force_register_subject(subject)
     }
@@ -352,8 +362,8 @@ mod tests {
 
     #[test]
     fn assign_no_necessary_replacements() {
-        let subject_ids = vec![1_u32, 2, 3, 4];
-        let group_ids = vec![101_u32, 102, 103];
+        let subject_ids = [1_u32, 2, 3, 4];
+        let group_ids = [101_u32, 102, 103];
         let mut preferences: HashMap<u32, Vec<u32>> = HashMap::new();
         let preference_by_order = vec![group_ids[0], group_ids[1], group_ids[2]];
         preferences.insert(subject_ids[0], preference_by_order.clone());
@@ -424,8 +434,8 @@ mod tests {
 
     #[test]
     fn assign_complete_after_first_step_only_full() {
-        let subject_id = 1 as u32;
-        let group_id = 101 as u32;
+        let subject_id = 1_u32;
+        let group_id = 101_u32;
         let subject = TestSubject::new(subject_id, vec![group_id]);
         let group = DefaultGroup::new(group_id, 1);
         let subjects = [subject];
@@ -438,6 +448,55 @@ mod tests {
         assert!(group_ids_to_subject_ids[&group_id].contains(&subject_id));
     }
 
+    #[test]
+    fn assign_reserved_seats() {
+        use crate::groups::SeatReservation;
+        use crate::subjects::DefaultSubject;
+        let subject = |id: u32, preferences: &[u32], categories: &[(u32, u32)]| {
+            let preferences: HashMap<u32, u32> = preferences
+                .iter()
+                .enumerate()
+                .map(|(rank, group_id)| (*group_id, rank as u32))
+                .collect();
+            DefaultSubject::new(id, preferences, 3)
+                .with_categories(categories.iter().cloned().collect())
+        };
+        let subjects = [
+            subject(1, &[101, 102, 103], &[]),
+            subject(2, &[101, 102, 103], &[]),
+            subject(3, &[101, 102, 103], &[(1, 7)]),
+            subject(4, &[103, 102, 101], &[]),
+        ];
+        let groups = [
+            DefaultGroup::new(101, 2).with_reserved_seats(vec![SeatReservation::new(1, 7, 1)]),
+            DefaultGroup::new(102, 2),
+            DefaultGroup::new(103, 1).with_reserved_seats(vec![SeatReservation::new(1, 8, 1)]),
+        ];
+        let assignment = ProposeAndReject::assign(&subjects, &groups).unwrap();
+        let members = |group_id: u32| {
+            let mut members = assignment
+                .group_id_to_subjects_ids(&group_id)
+                .cloned()
+                .unwrap_or_default();
+            members.sort_unstable();
+            members
+        };
+        // Subject 3 keeps the reserved seat, and nobody may take the seat reserved in group 103
+        assert_eq!(vec![2, 3], members(101));
+        assert_eq!(vec![1, 4], members(102));
+        assert!(members(103).is_empty());
+
+        // The reserved seat is released once the other groups are full
+        let subjects = [subject(1, &[101, 102], &[]), subject(2, &[101, 102], &[])];
+        let groups = [
+            DefaultGroup::new(101, 1).with_reserved_seats(vec![SeatReservation::new(1, 7, 1)]),
+            DefaultGroup::new(102, 1),
+        ];
+        let assignment = ProposeAndReject::assign(&subjects, &groups).unwrap();
+        assert_eq!(Some(&vec![2]), assignment.group_id_to_subjects_ids(&101));
+        assert_eq!(Some(&vec![1]), assignment.group_id_to_subjects_ids(&102));
+    }
+
     #[test]
     fn assign_no_first_choice() {
         struct TestSubjectWithoutFirstChoice {
@@ -456,15 +515,15 @@ mod tests {
                 Self { id }
             }
         }
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
 
         let first_subject = TestSubjectWithoutFirstChoice::new(first_subject_id);
         let second_subject = TestSubjectWithoutFirstChoice::new(second_subject_id);
         let subjects = [first_subject, second_subject];
 
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
 
         let first_group = DefaultGroup::new(first_group_id, 1);
         let second_group = DefaultGroup::new(second_group_id, 1);
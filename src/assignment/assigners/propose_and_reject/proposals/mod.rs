@@ -57,6 +57,10 @@ impl<'a, S: Subject, G: Group> GrowingGroupRegistry<'a, S>
             Ok(())
         }
     }
+
+    fn release_reserved_seats(&mut self) {
+        self.delegate.release_reserved_seats();
+    }
 }
 
 impl<'a, S: Subject, G: Group> Group for ProposalHandlingGroupRegistry<'a, S, G> {
@@ -67,12 +71,19 @@ impl<'a, S: Subject, G: Group> Group for ProposalHandlingGroupRegistry<'a, S, G>
     fn capacity(&self) -> u32 {
         self.delegate.capacity()
     }
+
+    fn has_unlimited_capacity(&self) -> bool {
+        self.delegate.has_unlimited_capacity()
+    }
 }
 
 impl<'a, S: Subject, G: Group> GroupRegistry for ProposalHandlingGroupRegistry<'a, S, G> {
     fn full(&self) -> bool {
         self.delegate.full()
     }
+    fn number_of_members(&self) -> usize {
+        self.delegate.number_of_members()
+    }
     fn subjects_ids_to_group_id(&self) -> HashMap<u32, u32> {
         self.delegate.subjects_ids_to_group_id()
     }
@@ -90,13 +101,19 @@ impl<'a, S: Subject, G: Group> ProposalHandlingGroupRegistry<'a, S, G> {
         }
     }
 
-    /// Provides a membership offer if this group is either not full or the proposing subject
-    /// is more eager to be a member of this group then the currently most dissatisfied member.
+    /// Provides a membership offer if this group has a seat for the proposing subject or the proposing subject
+    /// is more eager to be a member of this group then the most dissatisfied member whose seat it can take.
     pub(super) fn handle_membership_proposal(&self, subject: &S) -> Option<MembershipOffer> {
-        let dissatisfaction_rating = subject.dissatisfaction(&self.delegate.id());
-        if (self.delegate.subjects.len() as u32) >= self.capacity() {
+        let id = self.delegate.id();
+        let dissatisfaction_rating = subject.dissatisfaction(&id);
+        if self.delegate.accepts(subject) {
+            Some(MembershipOffer::new(dissatisfaction_rating, None))
+        } else if dissatisfaction_rating >= self.highest_dissatisfaction {
+            None
+        } else {
+            let replaced_member = self.delegate.subjects[self.replaceable_member(subject)?];
             let dissatisfaction_improvement =
-                (dissatisfaction_rating as i64) - (self.highest_dissatisfaction as i64);
+                (dissatisfaction_rating as i64) - (replaced_member.dissatisfaction(&id) as i64);
             if dissatisfaction_improvement >= 0 {
                 None
             } else {
@@ -105,43 +122,80 @@ impl<'a, S: Subject, G: Group> ProposalHandlingGroupRegistry<'a, S, G> {
                     Some(dissatisfaction_improvement),
                 ))
             }
-        } else {
-            Some(MembershipOffer::new(dissatisfaction_rating, None))
         }
     }
 
+    // The group is overfull if some of its members can neither take a reserved seat they are eligible for nor a general seat.
     pub(super) fn overfull(&self) -> bool {
-        (self.delegate.subjects.len() as u32) > self.capacity()
+        self.delegate.unseated(&self.delegate.subjects) > 0
+    }
+
+    // The position of the least happy member the given subject can replace without leaving anyone without a seat.
+    fn replaceable_member(&self, subject: &S) -> Option<usize> {
+        let id = self.id();
+        (0..self.delegate.subjects.len())
+            .filter(|&position| {
+                // Without reserved seats any member may be replaced
+                !self.delegate.keeps_reserved_seats() || {
+                    let members: Vec<&S> = self
+                        .delegate
+                        .subjects
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _x)| *i != position)
+                        .map(|(_i, x)| *x)
+                        .chain(std::iter::once(subject))
+                        .collect();
+                    self.delegate.unseated(&members) == 0
+                }
+            })
+            .max_by_key(|&position| self.delegate.subjects[position].dissatisfaction(&id))
+    }
+
+    // Whether removing the member at the given position leaves fewer members without a seat.
+    fn relieved_by_removal(&self, position: usize) -> bool {
+        // Without reserved seats any member may leave
+        if !self.delegate.keeps_reserved_seats() {
+            return true;
+        }
+        let members: Vec<&S> = self
+            .delegate
+            .subjects
+            .iter()
+            .enumerate()
+            .filter(|(i, _x)| *i != position)
+            .map(|(_i, x)| *x)
+            .collect();
+        self.delegate.unseated(&members) < self.delegate.unseated(&self.delegate.subjects)
     }
 
     /// Propose to another group to take a member from the current group.
-    /// If the other group is not full a transferral offer referring to one of the subjects
-    /// who minds the transferral the least is provided. In the case where the other group is full
-    /// a transferal offer will only be provided if this group has a member who is more eager to be
-    /// in the other group than that groups currently most dissatisfied member.
+    /// Only members whose departure leaves fewer members without a seat are proposed, and among those
+    /// the best membership offer, typically for the subject who minds the transferral the least, is chosen.
+    /// In the case where the other group has no seat for the subject a transferal offer will only be provided if this group
+    /// has a member who is more eager to be in the other group than the most dissatisfied member of that group it can replace.
     pub(super) fn propose_transferral(&self, other: &Self) -> Option<TransferralOffer> {
-        let proposed_group_id = other.id();
-        if let Some((lookup_key, Some(membership_offer))) = self
-            .delegate
+        self.delegate
             .subjects
             .iter()
             .enumerate()
-            .min_by(|(_key1, x), (_key2, y)| {
-                x.dissatisfaction(&proposed_group_id)
-                    .cmp(&y.dissatisfaction(&proposed_group_id))
+            .filter(|(key, _subject)| self.relieved_by_removal(*key))
+            .filter_map(|(key, subject)| {
+                other
+                    .handle_membership_proposal(subject)
+                    .map(|membership_offer| (key, membership_offer))
+            })
+            .min_by(|(_key1, x), (_key2, y)| x.cmp(y))
+            .map(|(lookup_key, membership_offer)| {
+                TransferralOffer::new(lookup_key, membership_offer)
             })
-            .map(|(key, subject)| (key, other.handle_membership_proposal(subject)))
-        {
-            Some(TransferralOffer::new(lookup_key, membership_offer))
-        } else {
-            None
-        }
     }
 
-    // removes the least happy member from the group registry and adds another member.
+    // removes the least happy member the subject can replace from the group registry and adds the subject.
     // The removed member is returned.
     fn replace_least_happy_member(&mut self, subject: &'a S) -> &'a S {
-        let least_happy_member = self.delegate.subjects.pop().unwrap(); // The last entry is always least happy with this group
+        let position = self.replaceable_member(subject).unwrap();
+        let least_happy_member = self.delegate.subjects.remove(position);
         self.register_subject(subject).unwrap();
         least_happy_member
     }
@@ -171,7 +225,15 @@ impl<'a, S: Subject, G: Group> ProposalHandlingGroupRegistry<'a, S, G> {
         {
             self.delegate.subjects.insert(position, subject);
             // We assume that the subject will be happy to be added to this group and therefore use position over rposition.
+        } else {
+            // The subject is the least happy member and thus belongs at the end
+            self.delegate.subjects.push(subject);
         }
+        self.highest_dissatisfaction = self
+            .delegate
+            .subjects
+            .last()
+            .map_or(0, |x| x.dissatisfaction(&id));
     }
 }
 
@@ -198,15 +260,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn force_register_least_happy_subject() {
+        let group_id = 101_u32;
+        let happy_subject = TestSubject::new(1, vec![group_id]);
+        // The group is the second choice of this subject, hence it is less happy than the current member
+        let unhappy_subject = TestSubject::new(2, vec![102, group_id]);
+        let group = DefaultGroup::new(group_id, 1);
+        let mut group_registry = ProposalHandlingGroupRegistry::new(&group, vec![&happy_subject]);
+        group_registry.force_register_subject(&unhappy_subject);
+        assert_eq!(2, group_registry.number_of_members());
+        assert!(group_registry.overfull());
+        assert_eq!(1, group_registry.highest_dissatisfaction);
+    }
+
     #[test]
     fn propose_transferral_none() {
         // Subject id's:
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
-        let third_subject_id = 3 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
+        let third_subject_id = 3_u32;
         // Group id's
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
         // Subjects
         let first_subject =
             TestSubject::new(first_subject_id, vec![second_group_id, first_group_id]);
@@ -228,13 +304,13 @@ mod tests {
     #[test]
     fn propose_transferral_subject_replacement() {
         // Subject id's:
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
-        let third_subject_id = 3 as u32;
-        let fourth_subject_id = 4 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
+        let third_subject_id = 3_u32;
+        let fourth_subject_id = 4_u32;
         // Group id's
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
         // Subjects
         let first_subject =
             TestSubject::new(first_subject_id, vec![second_group_id, first_group_id]);
@@ -261,13 +337,13 @@ mod tests {
     #[test]
     fn propose_transferral_enough_space() {
         // Subject id's:
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
-        let third_subject_id = 3 as u32;
-        let fourth_subject_id = 4 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
+        let third_subject_id = 3_u32;
+        let fourth_subject_id = 4_u32;
         // Group id's
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
         // Subjects
         let first_subject =
             TestSubject::new(first_subject_id, vec![second_group_id, first_group_id]);
@@ -294,12 +370,12 @@ mod tests {
     #[test]
     fn handle_membership_proposal_group_not_full() {
         // Subject id's:
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
-        let third_subject_id = 3 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
+        let third_subject_id = 3_u32;
         // Group id's
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
         // Subjects
         let first_subject =
             TestSubject::new(first_subject_id, vec![second_group_id, first_group_id]);
@@ -321,12 +397,12 @@ mod tests {
     #[test]
     fn handle_membership_proposal_group_full() {
         // Subject id's:
-        let first_subject_id = 1 as u32;
-        let second_subject_id = 2 as u32;
-        let third_subject_id = 3 as u32;
+        let first_subject_id = 1_u32;
+        let second_subject_id = 2_u32;
+        let third_subject_id = 3_u32;
         // Group id's
-        let first_group_id = 101 as u32;
-        let second_group_id = 102 as u32;
+        let first_group_id = 101_u32;
+        let second_group_id = 102_u32;
         // Subjects
         let first_subject =
             TestSubject::new(first_subject_id, vec![second_group_id, first_group_id]);
@@ -106,14 +106,20 @@ mod tests {
         let no_misplacement = MembershipOffer::new(2, None);
         let with_misplacement = MembershipOffer::new(2, Some(4));
         assert!(no_misplacement < with_misplacement);
-        assert!(!(with_misplacement < no_misplacement));
-        assert!(!(no_misplacement == with_misplacement));
+        assert_ne!(
+            Some(Ordering::Less),
+            with_misplacement.partial_cmp(&no_misplacement)
+        );
+        assert!(!no_misplacement.eq(&with_misplacement));
         // Now check the same for the ordering on transferral offers.
         let transferral_offer_no_misplacement = TransferralOffer::new(1, no_misplacement);
         let transferral_offer_with_misplacement = TransferralOffer::new(2, with_misplacement);
         assert!(transferral_offer_no_misplacement < transferral_offer_with_misplacement);
-        assert!(!(transferral_offer_with_misplacement < transferral_offer_no_misplacement));
-        assert!(!(transferral_offer_no_misplacement == transferral_offer_with_misplacement));
+        assert_ne!(
+            Some(Ordering::Less),
+            transferral_offer_with_misplacement.partial_cmp(&transferral_offer_no_misplacement)
+        );
+        assert!(!transferral_offer_no_misplacement.eq(&transferral_offer_with_misplacement));
     }
 
     #[test]
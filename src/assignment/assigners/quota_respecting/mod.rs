@@ -0,0 +1,399 @@
+//! # Quota respecting
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) taking the [quotas](crate::Quota) of the groups into account.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::min_cost_flow::cheapest_placement;
+use crate::groups::effective_capacity;
+use crate::groups::{Quota, QuotaViolation};
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+
+/// Assigns such that the [quotas](crate::Group::quotas) of the groups are respected while keeping the total dissatisfaction low.
+///
+///
+/// First the subjects are assigned so that the total dissatisfaction is as low as possible without regard to quotas.
+/// Then subjects are moved to groups with free seats, or swapped with each other, as long as this reduces the total amount by which the quotas are violated,
+/// or keeps this amount unchanged while reducing the total dissatisfaction.
+///
+/// Quotas can not always be satisfied (there might for instance not be enough senior members to go around). Use
+/// [assign_reporting_violations](QuotaRespecting::assign_reporting_violations) to learn which quotas could not be satisfied.
+/// Quotas whose lower bound exceeds their upper bound, or whose proportions do not lie between 0 and 1, are rejected as invalid input.
+pub struct QuotaRespecting {}
+
+impl Assigner for QuotaRespecting {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::assign_reporting_violations_with_tie_breaking(subjects, groups, tie_breaking)
+            .map(|(assignment, _)| assignment)
+    }
+}
+
+impl QuotaRespecting {
+    /// Assign the given subjects to the given groups and report the quotas that could not be satisfied.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject, Quota};
+    /// use group_assignment::assigners::QuotaRespecting;
+    /// use std::collections::HashMap;
+    /// let seniority = 1_u32;
+    /// let senior = 1_u32;
+    /// let junior = 0_u32;
+    /// let prefer_first: HashMap<u32, u32> = [(101, 0), (102, 1)].iter().cloned().collect();
+    /// let subjects: Vec<DefaultSubject> = [senior, senior, junior, junior]
+    ///     .iter()
+    ///     .enumerate()
+    ///     .map(|(i, level)| {
+    ///         DefaultSubject::new(i as u32, prefer_first.clone(), 2)
+    ///             .with_categories([(seniority, *level)].iter().cloned().collect())
+    ///     })
+    ///     .collect();
+    /// let groups = [
+    ///     DefaultGroup::new(101, 2).with_quotas(vec![Quota::at_least(seniority, senior, 1)]),
+    ///     DefaultGroup::new(102, 2).with_quotas(vec![Quota::at_least(seniority, senior, 1)]),
+    /// ];
+    /// let (assignment, violations) =
+    ///     QuotaRespecting::assign_reporting_violations(&subjects, &groups).unwrap();
+    /// assert!(violations.is_empty());
+    /// // The two senior subjects end up in different groups
+    /// assert_ne!(
+    ///     assignment.subject_to_group_id(&subjects[0]),
+    ///     assignment.subject_to_group_id(&subjects[1])
+    /// );
+    /// ```
+    pub fn assign_reporting_violations<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+    ) -> Result<(Assignment, Vec<QuotaViolation>), AssignmentError> {
+        Self::assign_reporting_violations_with_tie_breaking(
+            subjects,
+            groups,
+            TieBreaking::default(),
+        )
+    }
+
+    /// Like [assign_reporting_violations](QuotaRespecting::assign_reporting_violations), but the initial assignment
+    /// (disregarding quotas) is chosen among the equally good ones according to the given [tie-breaking policy](TieBreaking).
+    pub fn assign_reporting_violations_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<(Assignment, Vec<QuotaViolation>), AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        if let Some(group) = groups
+            .iter()
+            .find(|g| g.quotas().iter().any(|q| !q.is_sensible()))
+        {
+            return Err(AssignmentError::InvalidInput {
+                reason: format!(
+                    "The group {} has a quota with contradictory or out of range bounds",
+                    group.id()
+                ),
+            });
+        }
+        let mut search =
+            QuotaSearch::new(subjects, groups, &TieBreaker::new(tie_breaking, groups))?;
+        search.improve();
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, group_index) in subjects.iter().zip(search.placement.iter()) {
+            group_registries[*group_index].subjects.push(subject);
+        }
+        let assignment = super::assign_from_group_registries(group_registries);
+        let violations = assignment.quota_violations(subjects, groups);
+        Ok((assignment, violations))
+    }
+}
+
+// Local search state. Subjects and groups are referred to by their index in the input slices.
+struct QuotaSearch {
+    dissatisfaction: Vec<Vec<i64>>,
+    capacities: Vec<usize>,
+    quotas: Vec<Vec<Quota>>,
+    // in_category[i][j][k] indicates whether the i'th subject belongs to the category of the k'th quota of the j'th group
+    in_category: Vec<Vec<Vec<bool>>>,
+    placement: Vec<usize>,
+    sizes: Vec<u32>,
+    // counts[j][k] is the number of members of the j'th group belonging to the category of its k'th quota
+    counts: Vec<Vec<u32>>,
+}
+
+impl QuotaSearch {
+    fn new<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaker: &TieBreaker,
+    ) -> Result<Self, AssignmentError> {
+        let dissatisfaction: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|s| {
+                groups
+                    .iter()
+                    .map(|g| s.dissatisfaction(&g.id()) as i64)
+                    .collect()
+            })
+            .collect();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let quotas: Vec<Vec<Quota>> = groups.iter().map(|g| g.quotas()).collect();
+        let in_category = subjects
+            .iter()
+            .map(|s| {
+                quotas
+                    .iter()
+                    .map(|group_quotas| {
+                        group_quotas
+                            .iter()
+                            .map(|q| s.category(&q.attribute()) == Some(q.category()))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let ranks: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|s| tie_breaker.ranks(s.id(), groups))
+            .collect();
+        let placement: Vec<usize> = cheapest_placement(
+            subjects.len(),
+            &capacities,
+            |i, j| Some(dissatisfaction[i][j]),
+            |i, j| ranks[i][j],
+            tie_breaker.seat_costs().as_deref(),
+        )
+        .into_iter()
+        .collect::<Option<_>>()
+        .ok_or_else(|| AssignmentError::Infeasible {
+            reason: "The groups do not offer enough seats for all subjects".to_string(),
+        })?;
+        let mut search = Self {
+            dissatisfaction,
+            capacities,
+            counts: quotas.iter().map(|q| vec![0; q.len()]).collect(),
+            quotas,
+            in_category,
+            sizes: vec![0; groups.len()],
+            placement: Vec::new(),
+        };
+        for (i, j) in placement.iter().enumerate() {
+            search.add(i, *j);
+        }
+        search.placement = placement;
+        Ok(search)
+    }
+
+    fn add(&mut self, subject: usize, group: usize) {
+        self.sizes[group] += 1;
+        for (count, matches) in self.counts[group]
+            .iter_mut()
+            .zip(self.in_category[subject][group].iter())
+        {
+            if *matches {
+                *count += 1;
+            }
+        }
+    }
+
+    fn remove(&mut self, subject: usize, group: usize) {
+        self.sizes[group] -= 1;
+        for (count, matches) in self.counts[group]
+            .iter_mut()
+            .zip(self.in_category[subject][group].iter())
+        {
+            if *matches {
+                *count -= 1;
+            }
+        }
+    }
+
+    // The total number of members by which the given group deviates from its quotas
+    fn penalty(&self, group: usize) -> i64 {
+        self.quotas[group]
+            .iter()
+            .zip(self.counts[group].iter())
+            .map(|(q, count)| {
+                let (deficit, excess) = q.deviation(*count, self.sizes[group]);
+                (deficit + excess) as i64
+            })
+            .sum()
+    }
+
+    // Change in (penalty, dissatisfaction) if the given subjects leave and join the given groups.
+    // The changes are applied and must be undone by the caller if they are not wanted.
+    fn apply(&mut self, changes: &[(usize, usize, usize)]) -> (i64, i64) {
+        let mut affected: Vec<usize> = changes.iter().flat_map(|(_, a, b)| vec![*a, *b]).collect();
+        affected.sort_unstable();
+        affected.dedup();
+        let before: i64 = affected.iter().map(|j| self.penalty(*j)).sum();
+        let mut dissatisfaction_change = 0;
+        for (subject, from, to) in changes {
+            self.remove(*subject, *from);
+            self.add(*subject, *to);
+            self.placement[*subject] = *to;
+            dissatisfaction_change +=
+                self.dissatisfaction[*subject][*to] - self.dissatisfaction[*subject][*from];
+        }
+        let after: i64 = affected.iter().map(|j| self.penalty(*j)).sum();
+        (after - before, dissatisfaction_change)
+    }
+
+    fn try_changes(&mut self, changes: &[(usize, usize, usize)]) -> bool {
+        if self.apply(changes) < (0, 0) {
+            true
+        } else {
+            let reverted: Vec<(usize, usize, usize)> =
+                changes.iter().rev().map(|(s, a, b)| (*s, *b, *a)).collect();
+            self.apply(&reverted);
+            false
+        }
+    }
+
+    // Applies improving moves and swaps until none are left.
+    fn improve(&mut self) {
+        let (number_of_subjects, number_of_groups) = (self.placement.len(), self.sizes.len());
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..number_of_subjects {
+                for to in 0..number_of_groups {
+                    let from = self.placement[i];
+                    if to != from
+                        && (self.sizes[to] as usize) < self.capacities[to]
+                        && self.try_changes(&[(i, from, to)])
+                    {
+                        improved = true;
+                    }
+                }
+            }
+            for i in 0..number_of_subjects {
+                for k in (i + 1)..number_of_subjects {
+                    let (a, b) = (self.placement[i], self.placement[k]);
+                    if a != b && self.try_changes(&[(i, a, b), (k, b, a)]) {
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::{DefaultGroup, QuotaBound};
+    use crate::subjects::DefaultSubject;
+    use std::collections::HashMap;
+
+    const DEPARTMENT: u32 = 1;
+
+    fn subject(id: u32, preferences: &[(u32, u32)], department: u32) -> DefaultSubject {
+        DefaultSubject::new(id, preferences.iter().cloned().collect(), 10)
+            .with_categories([(DEPARTMENT, department)].iter().cloned().collect())
+    }
+
+    #[test]
+    fn assign_respects_maximal_proportion() {
+        let group_ids = [101_u32, 102];
+        let preferences = [(group_ids[0], 0), (group_ids[1], 1)];
+        // Three physicists (department 7) and one chemist (department 8) all preferring the first group
+        let subjects = [
+            subject(1, &preferences, 7),
+            subject(2, &preferences, 7),
+            subject(3, &preferences, 7),
+            subject(4, &preferences, 8),
+        ];
+        let at_most_half_physicists =
+            Quota::new(DEPARTMENT, 7, QuotaBound::Proportion { min: 0.0, max: 0.5 });
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 2).with_quotas(vec![at_most_half_physicists.clone()]))
+            .collect();
+        let (assignment, violations) =
+            QuotaRespecting::assign_reporting_violations(&subjects, &groups).unwrap();
+        // Both groups have two members, so one of them necessarily consists of physicists only
+        assert_eq!(1, violations.len());
+        assert_eq!(2, violations[0].members_in_category());
+        let (subject_ids_to_group_ids, _): (HashMap<u32, u32>, HashMap<u32, Vec<u32>>) =
+            assignment.into();
+        // The chemist is placed with a physicist so that only one group violates its quota
+        let chemist_group = subject_ids_to_group_ids[&4];
+        assert_ne!(violations[0].group_id(), chemist_group);
+    }
+
+    #[test]
+    fn assign_rejects_contradictory_quotas() {
+        let subjects = [subject(1, &[(101, 0)], 7)];
+        let contradictory =
+            Quota::new(DEPARTMENT, 7, QuotaBound::Proportion { min: 0.8, max: 0.2 });
+        let groups = [DefaultGroup::new(101, 1).with_quotas(vec![contradictory])];
+        assert!(matches!(
+            QuotaRespecting::assign(&subjects, &groups),
+            Err(AssignmentError::InvalidInput { .. })
+        ));
+    }
+
+    #[test]
+    fn assign_minimises_dissatisfaction_without_quotas() {
+        let group_ids = [101_u32, 102];
+        let subjects = [
+            subject(1, &[(group_ids[0], 0), (group_ids[1], 1)], 7),
+            subject(2, &[(group_ids[0], 0), (group_ids[1], 5)], 7),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+        ];
+        let (assignment, violations) =
+            QuotaRespecting::assign_reporting_violations(&subjects, &groups).unwrap();
+        assert!(violations.is_empty());
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+    }
+
+    #[test]
+    fn assign_meets_minimal_count_at_lowest_cost() {
+        let group_ids = [101_u32, 102];
+        // Everyone prefers the first group, but the second group needs a chemist (department 8)
+        let subjects = [
+            subject(1, &[(group_ids[0], 0), (group_ids[1], 1)], 8),
+            subject(2, &[(group_ids[0], 0), (group_ids[1], 3)], 8),
+            subject(3, &[(group_ids[0], 0), (group_ids[1], 1)], 7),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 2),
+            DefaultGroup::new(group_ids[1], 2).with_quotas(vec![Quota::at_least(DEPARTMENT, 8, 1)]),
+        ];
+        let (assignment, violations) =
+            QuotaRespecting::assign_reporting_violations(&subjects, &groups).unwrap();
+        assert!(violations.is_empty());
+        assert_eq!(
+            Some(&group_ids[1]),
+            assignment.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[1])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            assignment.subject_to_group_id(&subjects[2])
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! # Rank maximal
+//! This module provides an [assigner](crate::assignment::assigners::Assigner) giving as many subjects as possible their first choice, then their second choice and so on.
+use super::errors;
+use super::Assigner;
+use super::AssignmentError;
+use super::SimpleGroupRegistry;
+use super::{TieBreaker, TieBreaking};
+use crate::assignment::min_cost_flow::{LexicographicCost, MinCostFlow};
+use crate::groups::effective_capacity;
+use crate::subjects::Subject;
+use crate::{assignment::Assignment, groups::Group};
+
+/// How many subjects are assigned to their first, second, third, ... choice.
+///
+/// A subject's choices are derived from its dissatisfaction with the given groups: its first choices are the groups it is least dissatisfied with,
+/// its second choices are the groups with the next higher dissatisfaction, and so on. Equally rated groups are equally good choices.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RankSignature {
+    counts: Vec<usize>,
+}
+
+impl RankSignature {
+    /// The signature of the given assignment. Subjects that are not assigned to one of the given groups are not counted.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed, RankSignature};
+    /// let prefer_first = [(101, 0), (102, 1)].iter().cloned().collect();
+    /// let subjects = [
+    ///     DefaultSubject::new(1, prefer_first, 2),
+    ///     // Only the relative order matters: this subject's first choice is the second group
+    ///     DefaultSubject::new(2, [(101, 20), (102, 10)].iter().cloned().collect(), 30),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+    /// let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// assert_eq!(&vec![2], RankSignature::new(&assignment, &subjects, &groups).counts());
+    /// ```
+    pub fn new<S: Subject, G: Group>(
+        assignment: &Assignment,
+        subjects: &[S],
+        groups: &[G],
+    ) -> Self {
+        let mut counts = Vec::new();
+        for subject in subjects {
+            let group = assignment
+                .subject_to_group_id(subject)
+                .and_then(|id| groups.iter().position(|g| g.id() == *id));
+            if let Some(j) = group {
+                let rank = choice_ranks(subject, groups)[j];
+                if counts.len() <= rank {
+                    counts.resize(rank + 1, 0);
+                }
+                counts[rank] += 1;
+            }
+        }
+        Self { counts }
+    }
+
+    /// The number of subjects assigned to their first choice, their second choice and so on. Trailing zeros are omitted.
+    pub fn counts(&self) -> &Vec<usize> {
+        &self.counts
+    }
+}
+
+// For every group the number of distinct dissatisfaction ratings among the groups that are lower than the rating of the group, so 0 for first choices.
+fn choice_ranks<S: Subject, G: Group>(subject: &S, groups: &[G]) -> Vec<usize> {
+    let dissatisfaction: Vec<u32> = groups
+        .iter()
+        .map(|g| subject.dissatisfaction(&g.id()))
+        .collect();
+    let mut ratings = dissatisfaction.clone();
+    ratings.sort_unstable();
+    ratings.dedup();
+    dissatisfaction
+        .iter()
+        .map(|d| ratings.partition_point(|x| x < d))
+        .collect()
+}
+
+/// Assigns such that the [rank signature](RankSignature) is as large as possible in lexicographic order:
+/// as many subjects as possible get their first choice, then as many subjects as possible get their second choice, and so on.
+///
+///
+/// The assignment is found as a minimum cost flow whose costs are compared lexicographically, so that arbitrarily many choices can be distinguished without
+/// scaling the costs.
+///
+/// Use [assign_reporting_signature](RankMaximal::assign_reporting_signature) to learn the achieved signature.
+pub struct RankMaximal {}
+
+impl Assigner for RankMaximal {
+    fn assign_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<Assignment, AssignmentError> {
+        Self::assign_reporting_signature_with_tie_breaking(subjects, groups, tie_breaking)
+            .map(|(assignment, _)| assignment)
+    }
+}
+
+impl RankMaximal {
+    /// Assign the given subjects to the given groups and report the achieved rank signature.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, Incremental, RankMaximal, RankSignature};
+    /// let subjects = [
+    ///     DefaultSubject::new(1, [(101, 0), (102, 1), (103, 9)].iter().cloned().collect(), 9),
+    ///     DefaultSubject::new(2, [(101, 0), (102, 1), (103, 9)].iter().cloned().collect(), 9),
+    ///     DefaultSubject::new(3, [(102, 0), (103, 1), (101, 2)].iter().cloned().collect(), 9),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1), DefaultGroup::new(103, 1)];
+    /// let (assignment, signature) = RankMaximal::assign_reporting_signature(&subjects, &groups).unwrap();
+    /// // Two first choices are only possible if one of the first two subjects gets its last choice
+    /// assert_eq!(&vec![2, 0, 1], signature.counts());
+    /// assert_eq!(Some(&102), assignment.subject_to_group_id(&subjects[2]));
+    /// // Keeping the total dissatisfaction low yields a smaller signature
+    /// let cheapest = Incremental::assign(&subjects, &groups).unwrap();
+    /// assert_eq!(&vec![1, 2], RankSignature::new(&cheapest, &subjects, &groups).counts());
+    /// ```
+    pub fn assign_reporting_signature<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+    ) -> Result<(Assignment, RankSignature), AssignmentError> {
+        Self::assign_reporting_signature_with_tie_breaking(subjects, groups, TieBreaking::default())
+    }
+
+    /// Like [assign_reporting_signature](RankMaximal::assign_reporting_signature), but among the assignments with the largest signature
+    /// one is chosen according to the given [tie-breaking policy](TieBreaking).
+    pub fn assign_reporting_signature_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<(Assignment, RankSignature), AssignmentError> {
+        Self::unique_ids(subjects, groups)?;
+        Self::sufficient_capacity(subjects, groups)?;
+        errors::no_reserved_seats(groups)?;
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let ranks: Vec<Vec<usize>> = subjects.iter().map(|s| choice_ranks(s, groups)).collect();
+        let number_of_ranks = ranks.iter().flatten().map(|r| r + 1).max().unwrap_or(0);
+        // Costs count the subjects per choice (negatively, as more is better), followed by the costs of the tie-breaking policy
+        let seat_component = number_of_ranks;
+        let tie_component = seat_component + 1;
+        let cost = |components: &[(usize, i64)]| {
+            let mut cost = vec![0; tie_component + 1];
+            for (component, value) in components {
+                cost[*component] = *value;
+            }
+            LexicographicCost(cost)
+        };
+
+        let (number_of_subjects, number_of_groups) = (subjects.len(), groups.len());
+        let source = number_of_subjects + number_of_groups;
+        let sink = source + 1;
+        let mut network: MinCostFlow<LexicographicCost> = MinCostFlow::new(sink + 1);
+        let mut subject_edges: Vec<Vec<usize>> = Vec::with_capacity(number_of_subjects);
+        for (i, subject) in subjects.iter().enumerate() {
+            network.add_edge(source, i, 1, LexicographicCost::default());
+            let tie_ranks = tie_breaker.ranks(subject.id(), groups);
+            subject_edges.push(
+                (0..number_of_groups)
+                    .map(|j| {
+                        network.add_edge(
+                            i,
+                            number_of_subjects + j,
+                            1,
+                            cost(&[(ranks[i][j], -1), (tie_component, tie_ranks[j])]),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        let seat_costs = tie_breaker.seat_costs();
+        for (j, group) in groups.iter().enumerate() {
+            let seats = effective_capacity(group, number_of_subjects);
+            match seat_costs.as_deref() {
+                Some(seat_cost) => {
+                    for k in 0..seats {
+                        network.add_edge(
+                            number_of_subjects + j,
+                            sink,
+                            1,
+                            cost(&[(seat_component, seat_cost(j, k))]),
+                        );
+                    }
+                }
+                None => {
+                    network.add_edge(
+                        number_of_subjects + j,
+                        sink,
+                        seats as i64,
+                        LexicographicCost::default(),
+                    );
+                }
+            }
+        }
+        // Capacity is sufficient, hence every subject gets placed.
+        network.run(source, sink, number_of_subjects as i64);
+
+        let mut group_registries: Vec<_> = groups
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, edges) in subjects.iter().zip(subject_edges.iter()) {
+            if let Some(j) = edges.iter().position(|edge| network.flow(*edge) > 0) {
+                group_registries[j].subjects.push(subject);
+            }
+        }
+        let assignment = super::assign_from_group_registries(group_registries);
+        let signature = RankSignature::new(&assignment, subjects, groups);
+        Ok((assignment, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn assign_many_choices() {
+        // With twelve groups scaled costs would need to distinguish 13^12 > 2^44 combinations of choices
+        let group_ids: Vec<u32> = (101..113).collect();
+        let subjects: Vec<TestSubject> = (0..12)
+            .map(|id| {
+                let mut preferences = group_ids.clone();
+                preferences.rotate_left(id as usize / 2);
+                TestSubject::new(id, preferences)
+            })
+            .collect();
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 1))
+            .collect();
+        let (assignment, signature) =
+            RankMaximal::assign_reporting_signature(&subjects, &groups).unwrap();
+        // Two subjects share each of the first six groups as first choice. Of the subjects left over, the one with the
+        // sixth group as first choice gets its second choice, which pushes each of the others two choices further down.
+        assert_eq!(
+            &vec![6, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1],
+            signature.counts()
+        );
+        assert!(assignment.validate(&subjects, &groups).is_empty());
+    }
+}
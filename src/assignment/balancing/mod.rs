@@ -0,0 +1,138 @@
+//! # Balancing
+//! This module evens out the sizes of groups after an assignment has been made, for instance when several groups are parallel sections of the same course.
+use super::Assignment;
+use crate::groups::{Group, UNLIMITED_CAPACITY};
+use crate::subjects::Subject;
+
+impl Assignment {
+    /// Moves subjects from fuller to emptier groups without increasing the dissatisfaction of any subject.
+    ///
+    /// A subject is only moved to a group with a free seat that it is at most as dissatisfied with as with its current group,
+    /// and only if the move makes the fill ratios (members divided by capacity) of the two groups more even.
+    /// Subjects are considered in the given order and moved to the emptiest such group (the first one in the given groups in case of ties),
+    /// until no subject can be moved anymore. Subjects that are not assigned, or assigned to a group not among the given groups, are left alone.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed};
+    /// use std::collections::HashMap;
+    /// let indifferent: HashMap<u32, u32> = [(101, 0), (102, 0)].iter().cloned().collect();
+    /// let subjects: Vec<DefaultSubject> =
+    ///     (1..=4).map(|id| DefaultSubject::new(id, indifferent.clone(), 0)).collect();
+    /// let groups = [DefaultGroup::new(101, 4), DefaultGroup::new(102, 4)];
+    /// // First come first served packs everyone into the first group
+    /// let packed = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// assert_eq!(4, packed.group_id_to_subjects_ids(&101).unwrap().len());
+    /// let balanced = packed.balance(&subjects, &groups);
+    /// assert_eq!(2, balanced.group_id_to_subjects_ids(&101).unwrap().len());
+    /// assert_eq!(2, balanced.group_id_to_subjects_ids(&102).unwrap().len());
+    /// ```
+    pub fn balance<S: Subject, G: Group>(&self, subjects: &[S], groups: &[G]) -> Assignment {
+        let mut assignment = self.clone();
+        // Groups with unlimited capacity are treated as having a huge capacity, hence they count as (almost) empty.
+        let capacities: Vec<u128> = groups
+            .iter()
+            .map(|g| {
+                if g.has_unlimited_capacity() {
+                    UNLIMITED_CAPACITY as u128
+                } else {
+                    g.capacity() as u128
+                }
+            })
+            .collect();
+        let mut sizes: Vec<u128> = groups
+            .iter()
+            .map(|g| {
+                assignment
+                    .group_ids_to_subjects_ids
+                    .get(&g.id())
+                    .map_or(0, |x| x.len() as u128)
+            })
+            .collect();
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for subject in subjects {
+                let current = match assignment
+                    .subject_ids_to_group_ids
+                    .get(&subject.id())
+                    .and_then(|id| groups.iter().position(|g| g.id() == *id))
+                {
+                    Some(current) => current,
+                    None => continue,
+                };
+                let dissatisfaction = subject.dissatisfaction(&groups[current].id());
+                // Moving from a to b strictly decreases the sum of size^2 / capacity over all groups if and only if
+                // (2 * size_b + 1) / capacity_b < (2 * size_a - 1) / capacity_a. As this sum can not decrease forever, the loop terminates.
+                let destination = (0..groups.len())
+                    .filter(|j| {
+                        *j != current
+                            && sizes[*j] < capacities[*j]
+                            && subject.dissatisfaction(&groups[*j].id()) <= dissatisfaction
+                            && (2 * sizes[*j] + 1) * capacities[current]
+                                < (2 * sizes[current]).saturating_sub(1) * capacities[*j]
+                    })
+                    .min_by(|x, y| {
+                        ((2 * sizes[*x] + 1) * capacities[*y])
+                            .cmp(&((2 * sizes[*y] + 1) * capacities[*x]))
+                    });
+                if let Some(destination) = destination {
+                    assignment.unassign_subject(subject.id());
+                    assignment.assign_subject(subject.id(), groups[destination].id());
+                    sizes[current] = sizes[current].saturating_sub(1);
+                    sizes[destination] += 1;
+                    moved = true;
+                }
+            }
+        }
+        assignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assignment::assigners::{Assigner, FirstComeFirstServed, TieBreaking};
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn balance_respects_dissatisfaction_and_capacities() {
+        let group_ids = [101_u32, 102, 103];
+        // The first two subjects only want the first group, the others are indifferent between the first two groups
+        let mut subjects = vec![
+            TestSubject::new(1, vec![group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[0]]),
+        ];
+        subjects.extend((3..=8).map(|id| TestSubject::new(id, vec![])));
+        let groups = [
+            DefaultGroup::new(group_ids[0], 8),
+            DefaultGroup::new(group_ids[1], 4),
+            DefaultGroup::new(group_ids[2], 0),
+        ];
+        let packed = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let balanced = packed.balance(&subjects, &groups);
+        let size = |id: u32| {
+            balanced
+                .group_id_to_subjects_ids(&id)
+                .map_or(0, |x| x.len())
+        };
+        // Fill ratios 5/8 and 3/4 are as even as it gets
+        assert_eq!((5, 3, 0), (size(101), size(102), size(103)));
+        assert_eq!(
+            Some(&group_ids[0]),
+            balanced.subject_to_group_id(&subjects[0])
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            balanced.subject_to_group_id(&subjects[1])
+        );
+        // Balancing an assignment made with the matching tie-breaking policy changes nothing
+        let spread = FirstComeFirstServed::assign_with_tie_breaking(
+            &subjects,
+            &groups,
+            TieBreaking::LowestFillRatio,
+        )
+        .unwrap();
+        assert_eq!(spread, spread.balance(&subjects, &groups));
+    }
+}
@@ -0,0 +1,222 @@
+//! # Comparison
+//! This module provides differences between two [assignments](crate::Assignment),
+//! for instance the outputs of two assigners or of this week's and last week's run.
+use super::Assignment;
+use crate::subjects::Subject;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A subject that is assigned to a different group by one assignment than by another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    subject_id: u32,
+    from_group_id: u32,
+    to_group_id: u32,
+}
+
+impl Move {
+    pub(crate) fn new(subject_id: u32, from_group_id: u32, to_group_id: u32) -> Self {
+        Self {
+            subject_id,
+            from_group_id,
+            to_group_id,
+        }
+    }
+
+    /// The id of the subject that moved.
+    pub fn subject_id(&self) -> u32 {
+        self.subject_id
+    }
+
+    /// The id of the group the subject was previously assigned to.
+    pub fn from_group_id(&self) -> u32 {
+        self.from_group_id
+    }
+
+    /// The id of the group the subject is now assigned to.
+    pub fn to_group_id(&self) -> u32 {
+        self.to_group_id
+    }
+}
+
+/// The changes in the membership of a single group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupChange {
+    group_id: u32,
+    joined: Vec<u32>,
+    left: Vec<u32>,
+}
+
+impl GroupChange {
+    /// The id of the group.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// The ids of the subjects that are members of the group in the new assignment only, in ascending order.
+    pub fn joined(&self) -> &Vec<u32> {
+        &self.joined
+    }
+
+    /// The ids of the subjects that are members of the group in the old assignment only, in ascending order.
+    pub fn left(&self) -> &Vec<u32> {
+        &self.left
+    }
+}
+
+/// The differences between an old and a new assignment.
+///
+/// All lists are ordered by ascending subject, respectively group, ids so that diffs are reproducible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignmentDiff {
+    moves: Vec<Move>,
+    added: Vec<u32>,
+    removed: Vec<u32>,
+    group_changes: Vec<GroupChange>,
+}
+
+impl AssignmentDiff {
+    /// The subjects that are assigned by both assignments, but to different groups.
+    pub fn moves(&self) -> &Vec<Move> {
+        &self.moves
+    }
+
+    /// The ids of the subjects that are only assigned by the new assignment.
+    pub fn added(&self) -> &Vec<u32> {
+        &self.added
+    }
+
+    /// The ids of the subjects that are only assigned by the old assignment.
+    pub fn removed(&self) -> &Vec<u32> {
+        &self.removed
+    }
+
+    /// The groups whose members differ between the two assignments.
+    pub fn group_changes(&self) -> &Vec<GroupChange> {
+        &self.group_changes
+    }
+
+    /// Indicates whether the two assignments agree.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// For every move of one of the given subjects: the subject's id together with its dissatisfaction with the new group minus its dissatisfaction with the old group.
+    /// Negative values thus indicate subjects that are better off with the new assignment.
+    pub fn dissatisfaction_deltas<S: Subject>(&self, subjects: &[S]) -> Vec<(u32, i64)> {
+        let subjects_by_id: BTreeMap<u32, &S> = subjects.iter().map(|s| (s.id(), s)).collect();
+        self.moves
+            .iter()
+            .filter_map(|m| {
+                subjects_by_id.get(&m.subject_id).map(|s| {
+                    (
+                        m.subject_id,
+                        s.dissatisfaction(&m.to_group_id) as i64
+                            - s.dissatisfaction(&m.from_group_id) as i64,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// The sum of the [dissatisfaction deltas](AssignmentDiff::dissatisfaction_deltas) of the given subjects.
+    pub fn total_dissatisfaction_delta<S: Subject>(&self, subjects: &[S]) -> i64 {
+        self.dissatisfaction_deltas(subjects)
+            .iter()
+            .map(|(_, delta)| delta)
+            .sum()
+    }
+}
+
+impl Assignment {
+    /// Compares this (old) assignment with a new one.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed, ProposeAndReject};
+    /// use std::collections::HashMap;
+    /// let subjects = [
+    ///     DefaultSubject::new(1, [(101, 0), (102, 1)].iter().cloned().collect(), 2),
+    ///     DefaultSubject::new(2, [(101, 0), (102, 5)].iter().cloned().collect(), 6),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+    /// let first_come_first_served = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// let propose_and_reject = ProposeAndReject::assign(&subjects, &groups).unwrap();
+    /// let diff = first_come_first_served.diff(&propose_and_reject);
+    /// assert_eq!(2, diff.moves().len());
+    /// // The second subject gains more than the first subject loses
+    /// assert_eq!(-4, diff.total_dissatisfaction_delta(&subjects));
+    /// ```
+    pub fn diff(&self, new: &Assignment) -> AssignmentDiff {
+        let old_subjects: BTreeSet<u32> = self.subject_ids_to_group_ids.keys().copied().collect();
+        let new_subjects: BTreeSet<u32> = new.subject_ids_to_group_ids.keys().copied().collect();
+        let moves = old_subjects
+            .intersection(&new_subjects)
+            .filter_map(|id| {
+                let (from, to) = (
+                    self.subject_ids_to_group_ids[id],
+                    new.subject_ids_to_group_ids[id],
+                );
+                (from != to).then(|| Move::new(*id, from, to))
+            })
+            .collect();
+        let mut group_ids: BTreeSet<u32> = self.group_ids_to_subjects_ids.keys().copied().collect();
+        group_ids.extend(new.group_ids_to_subjects_ids.keys());
+        let members = |assignment: &Assignment, group_id: &u32| -> BTreeSet<u32> {
+            assignment
+                .group_ids_to_subjects_ids
+                .get(group_id)
+                .map(|ids| ids.iter().copied().collect())
+                .unwrap_or_default()
+        };
+        let group_changes = group_ids
+            .iter()
+            .map(|group_id| {
+                let (old_members, new_members) = (members(self, group_id), members(new, group_id));
+                GroupChange {
+                    group_id: *group_id,
+                    joined: new_members.difference(&old_members).copied().collect(),
+                    left: old_members.difference(&new_members).copied().collect(),
+                }
+            })
+            .filter(|x| !x.joined.is_empty() || !x.left.is_empty())
+            .collect();
+        AssignmentDiff {
+            moves,
+            added: new_subjects.difference(&old_subjects).copied().collect(),
+            removed: old_subjects.difference(&new_subjects).copied().collect(),
+            group_changes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::test_utils::assignment;
+
+    #[test]
+    fn diff() {
+        let old = assignment(&[(1, 101), (2, 101), (3, 102)]);
+        let new = assignment(&[(1, 102), (3, 102), (4, 101)]);
+        let diff = old.diff(&new);
+        assert_eq!(&vec![Move::new(1, 101, 102)], diff.moves());
+        assert_eq!(&vec![4], diff.added());
+        assert_eq!(&vec![2], diff.removed());
+        assert_eq!(
+            &vec![
+                GroupChange {
+                    group_id: 101,
+                    joined: vec![4],
+                    left: vec![1, 2]
+                },
+                GroupChange {
+                    group_id: 102,
+                    joined: vec![1],
+                    left: vec![]
+                }
+            ],
+            diff.group_changes()
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! # Efficiency
+//! This module checks whether an [assignment](crate::Assignment) is Pareto-efficient, that is whether no subject can be made better off without making another subject worse off.
+//! Assigners minimizing the total dissatisfaction, such as [Incremental](crate::assigners::Incremental), always produce Pareto-efficient assignments.
+use super::comparison::Move;
+use super::Assignment;
+use crate::groups::Group;
+use crate::subjects::Subject;
+use std::collections::VecDeque;
+
+/// A witness that an assignment is not Pareto-efficient: a set of moves after which no subject involved is worse off and at least one is better off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParetoImprovement {
+    /// Every subject takes the seat of the next subject, and the last subject takes the seat of the first one.
+    ExchangeCycle(Vec<Move>),
+    /// Every subject takes the seat of the next subject, and the last subject takes a free seat.
+    FreeSeatChain(Vec<Move>),
+}
+
+impl ParetoImprovement {
+    /// The moves making up the improvement, in the order described by the variants.
+    pub fn moves(&self) -> &Vec<Move> {
+        match self {
+            ParetoImprovement::ExchangeCycle(moves) => moves,
+            ParetoImprovement::FreeSeatChain(moves) => moves,
+        }
+    }
+
+    /// Applies the moves to the given assignment, which should be the assignment the improvement was found for.
+    pub fn apply(&self, assignment: &Assignment) -> Assignment {
+        let mut improved = assignment.clone();
+        for m in self.moves() {
+            improved.unassign_subject(m.subject_id());
+            improved.assign_subject(m.subject_id(), m.to_group_id());
+        }
+        improved
+    }
+}
+
+impl Assignment {
+    /// Checks whether the assignment is Pareto-efficient among the given subjects and groups. See [Assignment::pareto_improvement].
+    pub fn is_pareto_efficient<S: Subject, G: Group>(&self, subjects: &[S], groups: &[G]) -> bool {
+        self.pareto_improvement(subjects, groups).is_none()
+    }
+
+    /// Searches for a Pareto improvement of the assignment, moving assigned subjects among the given subjects between the given groups.
+    /// Returns `None` if the assignment is Pareto-efficient.
+    ///
+    /// The search uses the trading graph between groups: there is an edge from one group to another if a member of the first group
+    /// is at most as dissatisfied with the second group. A Pareto improvement exists if and only if some edge stemming from a subject that is
+    /// strictly less dissatisfied lies on a cycle, or on a path ending in a group with a free seat.
+    /// The returned witness visits every group at most once and is the shortest one for the first such edge found (by position of the groups).
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed, Incremental};
+    /// use group_assignment::efficiency::ParetoImprovement;
+    /// let subjects = [
+    ///     DefaultSubject::new(1, [(101, 0), (102, 0)].iter().cloned().collect(), 2),
+    ///     DefaultSubject::new(2, [(101, 0), (102, 1)].iter().cloned().collect(), 2),
+    /// ];
+    /// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+    /// let first_come_first_served = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// // The first subject takes the first group although it does not mind swapping with the second subject
+    /// let improvement = first_come_first_served.pareto_improvement(&subjects, &groups).unwrap();
+    /// assert!(matches!(improvement, ParetoImprovement::ExchangeCycle(_)));
+    /// let improved = improvement.apply(&first_come_first_served);
+    /// assert!(improved.is_pareto_efficient(&subjects, &groups));
+    /// assert!(Incremental::assign(&subjects, &groups).unwrap().is_pareto_efficient(&subjects, &groups));
+    /// ```
+    pub fn pareto_improvement<S: Subject, G: Group>(
+        &self,
+        subjects: &[S],
+        groups: &[G],
+    ) -> Option<ParetoImprovement> {
+        let positions: Vec<Option<usize>> = subjects
+            .iter()
+            .map(|s| {
+                self.subject_ids_to_group_ids
+                    .get(&s.id())
+                    .and_then(|id| groups.iter().position(|g| g.id() == *id))
+            })
+            .collect();
+        // Members that are not among the given subjects still occupy seats
+        let free: Vec<bool> = groups
+            .iter()
+            .map(|g| {
+                let members = self
+                    .group_ids_to_subjects_ids
+                    .get(&g.id())
+                    .map_or(0, |x| x.len());
+                g.has_unlimited_capacity() || members < g.capacity() as usize
+            })
+            .collect();
+        // edges[a][b] is the first subject in group a strictly preferring group b, or otherwise the first subject indifferent between them.
+        // The flag indicates whether the subject strictly prefers group b.
+        let mut edges: Vec<Vec<Option<(usize, bool)>>> =
+            vec![vec![None; groups.len()]; groups.len()];
+        for (i, (subject, position)) in subjects.iter().zip(positions.iter()).enumerate() {
+            if let Some(a) = *position {
+                let current = subject.dissatisfaction(&groups[a].id());
+                for (b, group) in groups.iter().enumerate().filter(|(b, _)| *b != a) {
+                    let dissatisfaction = subject.dissatisfaction(&group.id());
+                    if dissatisfaction <= current
+                        && edges[a][b]
+                            .is_none_or(|(_, strict)| !strict && dissatisfaction < current)
+                    {
+                        edges[a][b] = Some((i, dissatisfaction < current));
+                    }
+                }
+            }
+        }
+        for b in 0..groups.len() {
+            // Breadth first search from group b, remembering where every group was reached from
+            let mut predecessors: Vec<Option<usize>> = vec![None; groups.len()];
+            let mut reached = vec![false; groups.len()];
+            let mut queue = VecDeque::from([b]);
+            reached[b] = true;
+            while let Some(x) = queue.pop_front() {
+                for y in 0..groups.len() {
+                    if !reached[y] && edges[x][y].is_some() {
+                        reached[y] = true;
+                        predecessors[y] = Some(x);
+                        queue.push_back(y);
+                    }
+                }
+            }
+            let path_to = |mut end: usize| {
+                let mut path = vec![end];
+                while let Some(x) = predecessors[end] {
+                    path.push(x);
+                    end = x;
+                }
+                path.reverse();
+                path
+            };
+            let first_free = (0..groups.len())
+                .filter(|y| reached[*y] && free[*y])
+                .min_by_key(|y| path_to(*y).len());
+            for a in 0..groups.len() {
+                if !edges[a][b].is_some_and(|(_, strict)| strict) {
+                    continue;
+                }
+                // The groups visited, starting with a. Every group but the last one loses a member to the next one.
+                let (end, cycle) = match (reached[a], first_free) {
+                    (true, _) => (a, true),
+                    (false, Some(f)) => (f, false),
+                    (false, None) => continue,
+                };
+                let mut route = vec![a];
+                route.extend(path_to(end));
+                let moves: Vec<Move> = route
+                    .windows(2)
+                    .map(|x| {
+                        let (i, _) = edges[x[0]][x[1]].unwrap();
+                        Move::new(subjects[i].id(), groups[x[0]].id(), groups[x[1]].id())
+                    })
+                    .collect();
+                return Some(if cycle {
+                    ParetoImprovement::ExchangeCycle(moves)
+                } else {
+                    ParetoImprovement::FreeSeatChain(moves)
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::{Assigner, QuotaRespecting};
+    use crate::assignment::test_utils::{assignment, contested_instance};
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn pareto_improvement_exchange_cycle() {
+        let group_ids = [101_u32, 102, 103];
+        // The first two subjects want the group of the next subject, the third subject is indifferent
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[1], group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[2], group_ids[1]]),
+            TestSubject::new(3, vec![]),
+        ];
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 1))
+            .collect();
+        let initial = assignment(&[(1, group_ids[0]), (2, group_ids[1]), (3, group_ids[2])]);
+        let improvement = initial.pareto_improvement(&subjects, &groups).unwrap();
+        assert_eq!(
+            ParetoImprovement::ExchangeCycle(vec![
+                Move::new(1, group_ids[0], group_ids[1]),
+                Move::new(2, group_ids[1], group_ids[2]),
+                Move::new(3, group_ids[2], group_ids[0]),
+            ]),
+            improvement
+        );
+        let improved = improvement.apply(&initial);
+        assert!(improved.validate(&subjects, &groups).is_empty());
+        assert!(improved.is_pareto_efficient(&subjects, &groups));
+    }
+
+    #[test]
+    fn pareto_improvement_free_seat_chain() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[1], group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[2], group_ids[1]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+        ];
+        let initial = assignment(&[(1, group_ids[0]), (2, group_ids[1])]);
+        assert_eq!(
+            Some(ParetoImprovement::FreeSeatChain(vec![
+                Move::new(1, group_ids[0], group_ids[1]),
+                Move::new(2, group_ids[1], group_ids[2]),
+            ])),
+            initial.pareto_improvement(&subjects, &groups)
+        );
+        // Without the free seat the assignment is efficient
+        assert!(initial.is_pareto_efficient(&subjects, &groups[..2]));
+    }
+
+    #[test]
+    fn quota_respecting_is_pareto_efficient() {
+        let (subjects, groups) = contested_instance();
+        let assignment = QuotaRespecting::assign(&subjects, &groups).unwrap();
+        assert!(assignment.is_pareto_efficient(&subjects, &groups));
+    }
+}
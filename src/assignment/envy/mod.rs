@@ -0,0 +1,226 @@
+//! # Envy
+//! This module reports which subjects envy each other, that is which subjects are less dissatisfied with the group of another subject than with their own group.
+//!
+//! Envy alone is often unavoidable, as popular groups have limited capacities. Envy is justified if the envied subject has a lower priority for the group than the envious subject,
+//! hence the envious subject could claim the envied subject's seat.
+use super::random;
+use super::Assignment;
+use crate::subjects::Subject;
+use std::collections::BTreeMap;
+
+/// Decides which subjects have priority for a seat in a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PriorityPolicy {
+    /// Subjects that are less dissatisfied with the group have priority, just like groups of
+    /// [ProposeAndReject](crate::assigners::ProposeAndReject) discard their most dissatisfied members. Equally dissatisfied subjects have equal priority.
+    #[default]
+    Dissatisfaction,
+    /// Subjects have priority in the order they were given in, just like for [FirstComeFirstServed](crate::assigners::FirstComeFirstServed).
+    SubjectOrder,
+    /// Subjects have priority in a pseudo random order determined by the seed. The order is the same for every group.
+    Seeded(u64),
+}
+
+impl PriorityPolicy {
+    // Subjects with smaller keys have priority for the group. Subjects with equal keys have equal priority.
+    pub(crate) fn key<S: Subject>(&self, subject: &S, position: usize, group_id: &u32) -> u64 {
+        match self {
+            PriorityPolicy::Dissatisfaction => subject.dissatisfaction(group_id) as u64,
+            PriorityPolicy::SubjectOrder => position as u64,
+            PriorityPolicy::Seeded(seed) => random::mix(seed ^ random::mix(subject.id() as u64)),
+        }
+    }
+}
+
+/// A subject that would rather be in the group of another subject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Envy {
+    envious_subject_id: u32,
+    envied_subject_id: u32,
+    group_id: u32,
+    justified: bool,
+}
+
+impl Envy {
+    /// The id of the subject that would rather be in the other subject's group.
+    pub fn envious_subject_id(&self) -> u32 {
+        self.envious_subject_id
+    }
+
+    /// The id of the subject whose group is envied.
+    pub fn envied_subject_id(&self) -> u32 {
+        self.envied_subject_id
+    }
+
+    /// The id of the envied subject's group.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// Indicates whether the envied subject has a lower priority for its group than the envious subject.
+    pub fn is_justified(&self) -> bool {
+        self.justified
+    }
+}
+
+/// Who envies whom in an assignment.
+///
+/// All lists are ordered by ascending subject ids so that reports are reproducible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvyReport {
+    envies: Vec<Envy>,
+    envy_counts: Vec<(u32, usize)>,
+    envied_counts: Vec<(u32, usize)>,
+}
+
+impl EnvyReport {
+    /// Every pair of an envious and an envied subject, ordered by the envious and then by the envied subject's id.
+    pub fn envies(&self) -> &Vec<Envy> {
+        &self.envies
+    }
+
+    /// The envies that are [justified](Envy::is_justified).
+    pub fn justified_envies(&self) -> Vec<Envy> {
+        self.envies
+            .iter()
+            .filter(|x| x.justified)
+            .copied()
+            .collect()
+    }
+
+    /// For every assigned subject: its id together with the number of subjects it envies.
+    pub fn envy_counts(&self) -> &Vec<(u32, usize)> {
+        &self.envy_counts
+    }
+
+    /// For every assigned subject: its id together with the number of subjects envying it.
+    pub fn envied_counts(&self) -> &Vec<(u32, usize)> {
+        &self.envied_counts
+    }
+
+    /// Indicates whether no subject envies another one.
+    pub fn is_envy_free(&self) -> bool {
+        self.envies.is_empty()
+    }
+
+    /// Indicates whether no subject is justified in envying another one.
+    pub fn is_free_of_justified_envy(&self) -> bool {
+        self.envies.iter().all(|x| !x.justified)
+    }
+}
+
+impl Assignment {
+    /// Reports which of the given subjects envy each other, where priorities are decided by the given policy.
+    ///
+    /// Only assigned subjects take part: unassigned subjects neither envy nor are envied.
+    ///
+    /// ```
+    /// use group_assignment::DefaultSubject;
+    /// use group_assignment::Assignment;
+    /// use group_assignment::envy::PriorityPolicy;
+    /// use std::collections::HashMap;
+    /// let prefer_first: HashMap<u32, u32> = [(101, 0), (102, 1)].iter().cloned().collect();
+    /// let subjects = [
+    ///     DefaultSubject::new(1, HashMap::new(), 1),
+    ///     DefaultSubject::new(2, prefer_first, 2),
+    /// ];
+    /// let subject_ids_to_group_ids: HashMap<u32, u32> = [(1, 101), (2, 102)].iter().cloned().collect();
+    /// let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> = [(101, vec![1]), (102, vec![2])].iter().cloned().collect();
+    /// let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+    /// let report = assignment.envy_report(&subjects, PriorityPolicy::Dissatisfaction);
+    /// // The second subject envies the first one, who does not care about the first group
+    /// assert_eq!(1, report.envies().len());
+    /// assert_eq!(2, report.envies()[0].envious_subject_id());
+    /// assert!(report.envies()[0].is_justified());
+    /// // When subjects are prioritised in the given order, the first subject keeps its seat
+    /// assert!(assignment.envy_report(&subjects, PriorityPolicy::SubjectOrder).is_free_of_justified_envy());
+    /// ```
+    pub fn envy_report<S: Subject>(&self, subjects: &[S], policy: PriorityPolicy) -> EnvyReport {
+        let assigned: Vec<(usize, &S, u32)> = subjects
+            .iter()
+            .enumerate()
+            .filter_map(|(position, s)| {
+                self.subject_ids_to_group_ids
+                    .get(&s.id())
+                    .map(|group_id| (position, s, *group_id))
+            })
+            .collect();
+        let mut envies = Vec::new();
+        let mut envy_counts: BTreeMap<u32, usize> =
+            assigned.iter().map(|(_, s, _)| (s.id(), 0)).collect();
+        let mut envied_counts = envy_counts.clone();
+        for (envious_position, envious, own_group_id) in assigned.iter() {
+            for (envied_position, envied, group_id) in assigned.iter() {
+                if envious.dissatisfaction(group_id) >= envious.dissatisfaction(own_group_id) {
+                    continue;
+                }
+                let justified = policy.key(*envied, *envied_position, group_id)
+                    > policy.key(*envious, *envious_position, group_id);
+                envies.push(Envy {
+                    envious_subject_id: envious.id(),
+                    envied_subject_id: envied.id(),
+                    group_id: *group_id,
+                    justified,
+                });
+                *envy_counts.entry(envious.id()).or_default() += 1;
+                *envied_counts.entry(envied.id()).or_default() += 1;
+            }
+        }
+        envies.sort_unstable_by_key(|x| (x.envious_subject_id, x.envied_subject_id));
+        EnvyReport {
+            envies,
+            envy_counts: envy_counts.into_iter().collect(),
+            envied_counts: envied_counts.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::{Assigner, FirstComeFirstServed, ProposeAndReject};
+    use crate::assignment::test_utils::contested_instance;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn envy_report_counts_and_justification() {
+        let group_ids = [101_u32, 102];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[1], group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[1]]),
+            TestSubject::new(3, vec![group_ids[0], group_ids[1]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 2),
+        ];
+        // First come first served places the first subject in its favourite group, the third subject only gets its second choice
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let report = assignment.envy_report(&subjects, PriorityPolicy::SubjectOrder);
+        assert_eq!(
+            vec![Envy {
+                envious_subject_id: 3,
+                envied_subject_id: 2,
+                group_id: group_ids[0],
+                justified: false
+            }],
+            *report.envies()
+        );
+        assert_eq!(vec![(1, 0), (2, 0), (3, 1)], *report.envy_counts());
+        assert_eq!(vec![(1, 0), (2, 1), (3, 0)], *report.envied_counts());
+        assert!(!report.is_envy_free());
+        assert!(report.is_free_of_justified_envy());
+    }
+
+    #[test]
+    fn propose_and_reject_is_free_of_justified_envy() {
+        let (subjects, groups) = contested_instance();
+        let assignment = ProposeAndReject::assign(&subjects, &groups).unwrap();
+        let report = assignment.envy_report(&subjects, PriorityPolicy::Dissatisfaction);
+        // Some of the subjects wanting the smallest group envy its members, but none of them has priority
+        assert!(!report.is_envy_free());
+        assert!(report.is_free_of_justified_envy());
+    }
+}
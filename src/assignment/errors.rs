@@ -1,6 +1,7 @@
 //! # Assignment related errors
 //! Module for assignment related errors
 
+use crate::groups::Group;
 use std::fmt;
 #[derive(Debug, Clone)]
 // Error indicating that a group is already full while trying to add another subject.
@@ -14,11 +15,133 @@ impl fmt::Display for CapacityError {
     }
 }
 
-#[derive(Debug, Clone)]
-/// Error indicating that it is not possible to assign the given subjects to groups under the current capacity constraints.
-pub struct TotalCapacityError {}
-impl fmt::Display for TotalCapacityError {
+/// Errors returned by the [assigners](crate::assigners) and the other ways of forming groups in this library.
+///
+/// New kinds of errors may be added in the future, hence matches must include a wildcard arm.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Assigner, FirstComeFirstServed};
+/// use group_assignment::errors::AssignmentError;
+/// use std::collections::HashMap;
+/// let subjects = [DefaultSubject::new(1, HashMap::new(), 0), DefaultSubject::new(2, HashMap::new(), 0)];
+/// let groups = [DefaultGroup::new(101, 1)];
+/// match FirstComeFirstServed::assign(&subjects, &groups) {
+///     Err(AssignmentError::InsufficientCapacity { required, available }) => {
+///         assert_eq!((2, 1), (required, available))
+///     }
+///     _ => panic!("the capacity should be insufficient"),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AssignmentError {
+    /// The combined capacity of the groups is less than the number of subjects.
+    InsufficientCapacity { required: u64, available: u64 },
+    /// Several subjects or several groups share the same id. The duplicated ids are listed in ascending order.
+    DuplicateIds {
+        subject_ids: Vec<u32>,
+        group_ids: Vec<u32>,
+    },
+    /// The input is well formed, but the requested constraints can not all be satisfied.
+    Infeasible { reason: String },
+    /// An iterative procedure did not come to an end within the given number of iterations.
+    ///
+    /// Reserved for procedures with an iteration budget. None of the current assigners returns it, as all of them provably terminate.
+    NonTermination { iterations: usize },
+    /// The input (for instance the parameters of an assigner) does not make sense.
+    InvalidInput { reason: String },
+}
+
+impl fmt::Display for AssignmentError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Insufficient capacity: The combined group capacity is less than the number of subjects")
+        match self {
+            AssignmentError::InsufficientCapacity { required, available } => write!(
+                f,
+                "Insufficient capacity: The combined group capacity {} is less than the number of subjects {}",
+                available, required
+            ),
+            AssignmentError::DuplicateIds {
+                subject_ids,
+                group_ids,
+            } => write!(
+                f,
+                "Duplicate ids: The subject ids {:?} and the group ids {:?} are not unique",
+                subject_ids, group_ids
+            ),
+            AssignmentError::Infeasible { reason } => write!(f, "Infeasible: {}", reason),
+            AssignmentError::NonTermination { iterations } => write!(
+                f,
+                "Non-termination: No result was reached within {} iterations",
+                iterations
+            ),
+            AssignmentError::InvalidInput { reason } => write!(f, "Invalid input: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AssignmentError {}
+
+/// Checks that the given ids are unique and returns the ids occurring more than once otherwise.
+pub(crate) fn unique_ids(
+    subject_ids: impl Iterator<Item = u32>,
+    group_ids: impl Iterator<Item = u32>,
+) -> Result<(), AssignmentError> {
+    let (subject_ids, group_ids) = (duplicates(subject_ids), duplicates(group_ids));
+    if subject_ids.is_empty() && group_ids.is_empty() {
+        Ok(())
+    } else {
+        Err(AssignmentError::DuplicateIds {
+            subject_ids,
+            group_ids,
+        })
+    }
+}
+
+/// Checks that the combined capacity of the groups suffices for the given number of subjects.
+pub(crate) fn sufficient_capacity<G: Group>(
+    number_of_subjects: usize,
+    groups: &[G],
+) -> Result<(), AssignmentError> {
+    if groups.iter().any(|x| x.has_unlimited_capacity()) {
+        return Ok(());
+    }
+    let available = groups
+        .iter()
+        .fold(0_u64, |acc, x| acc.saturating_add(x.capacity() as u64));
+    let required = number_of_subjects as u64;
+    if available >= required {
+        Ok(())
+    } else {
+        Err(AssignmentError::InsufficientCapacity {
+            required,
+            available,
+        })
+    }
+}
+
+/// Checks that none of the groups reserves seats, for assigners that can not honour reservations.
+pub(crate) fn no_reserved_seats<G: Group>(groups: &[G]) -> Result<(), AssignmentError> {
+    match groups.iter().find(|x| !x.reserved_seats().is_empty()) {
+        Some(group) => Err(AssignmentError::InvalidInput {
+            reason: format!(
+                "The group {} reserves seats, which this assigner can not take into account",
+                group.id()
+            ),
+        }),
+        None => Ok(()),
     }
 }
+
+// The ids occurring more than once, in ascending order.
+fn duplicates(ids: impl Iterator<Item = u32>) -> Vec<u32> {
+    let mut ids: Vec<u32> = ids.collect();
+    ids.sort_unstable();
+    let mut duplicates: Vec<u32> = ids
+        .windows(2)
+        .filter(|x| x[0] == x[1])
+        .map(|x| x[0])
+        .collect();
+    duplicates.dedup();
+    duplicates
+}
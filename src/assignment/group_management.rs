@@ -1,7 +1,9 @@
 use crate::assignment::errors::CapacityError;
-use crate::groups::Group;
+use crate::assignment::tie_breaking::TieBreaker;
+use crate::groups::{seats_suffice, unseated, Group, SeatReservation, UNLIMITED_CAPACITY};
 use crate::subjects::Subject;
 use std::collections::HashMap;
+use std::iter;
 
 use super::Assignment;
 /// Trait for group membership management.
@@ -15,6 +17,9 @@ pub(super) trait GroupRegistry: Group {
 
     /// Indicates whether the managed group is full
     fn full(&self) -> bool;
+
+    /// The number of subjects currently registered to the group
+    fn number_of_members(&self) -> usize;
 }
 
 /// Transforms a vector of group registries into a pair of mappings representing group assignments.
@@ -50,6 +55,15 @@ pub(super) fn assign_from_group_registries<M: GroupRegistry>(
 /// Group registries with the ability to register new members
 pub(super) trait GrowingGroupRegistry<'a, S>: GroupRegistry {
     fn register_subject(&mut self, subject: &'a S) -> Result<(), CapacityError>;
+
+    /// Indicates whether the given subject can currently be registered.
+    /// Registries keeping seats reserved for other subjects may refuse a subject although they are not full.
+    fn accepts(&self, _subject: &S) -> bool {
+        !self.full()
+    }
+
+    /// Turns the reserved seats that have not been taken into seats available to every subject.
+    fn release_reserved_seats(&mut self) {}
 }
 
 pub(super) fn subject_to_best_available_group_registry<
@@ -59,14 +73,16 @@ pub(super) fn subject_to_best_available_group_registry<
 >(
     subject: &'a S,
     mut group_registries: Vec<M>,
+    tie_breaker: &TieBreaker,
 ) -> Vec<M> {
     if let Some(x) = group_registries
         .iter_mut()
-        .filter(|x| !x.full())
-        .min_by(|x, y| {
-            subject
-                .dissatisfaction(&x.id())
-                .cmp(&subject.dissatisfaction(&y.id()))
+        .filter(|x| x.accepts(subject))
+        .min_by_key(|x| {
+            (
+                subject.dissatisfaction(&x.id()),
+                tie_breaker.key(subject.id(), x.id(), x.number_of_members()),
+            )
         })
     {
         x.register_subject(subject).unwrap();
@@ -82,6 +98,8 @@ where
 {
     pub(super) group: &'a G,
     pub(super) subjects: Vec<&'a S>, // members to be assigned to the corresponding group
+    reserved_seats: Vec<SeatReservation>,
+    reserved_seats_released: bool,
 }
 
 impl<'a, S: Subject, G: Group> Group for SimpleGroupRegistry<'a, S, G> {
@@ -92,17 +110,72 @@ impl<'a, S: Subject, G: Group> Group for SimpleGroupRegistry<'a, S, G> {
     fn capacity(&self) -> u32 {
         self.group.capacity()
     }
+
+    fn has_unlimited_capacity(&self) -> bool {
+        self.group.has_unlimited_capacity()
+    }
 }
 
 impl<'a, S: Subject, G: Group> SimpleGroupRegistry<'a, S, G> {
     pub(super) fn new(group: &'a G, subjects: Vec<&'a S>) -> Self {
-        Self { group, subjects }
+        Self {
+            group,
+            subjects,
+            reserved_seats: group.reserved_seats(),
+            reserved_seats_released: false,
+        }
+    }
+
+    // The number of seats that are not reserved for anyone
+    fn general_seats(&self) -> u32 {
+        if self.has_unlimited_capacity() {
+            return UNLIMITED_CAPACITY;
+        }
+        let reserved: u32 = self
+            .reserved_seats
+            .iter()
+            .fold(0_u32, |acc, x| acc.saturating_add(x.seats()));
+        self.capacity().saturating_sub(reserved)
+    }
+
+    // Whether some seats of this group are currently kept for subjects of certain categories
+    pub(super) fn keeps_reserved_seats(&self) -> bool {
+        !(self.has_unlimited_capacity()
+            || self.reserved_seats_released
+            || self.reserved_seats.is_empty())
+    }
+
+    // The number of the given subjects that cannot be seated in this group, taking the reserved seats into account unless they have been released.
+    pub(super) fn unseated(&self, subjects: &[&S]) -> usize {
+        if self.keeps_reserved_seats() {
+            unseated(
+                subjects,
+                &self.reserved_seats,
+                self.general_seats() as usize,
+            )
+        } else if self.has_unlimited_capacity() {
+            0
+        } else {
+            subjects.len().saturating_sub(self.capacity() as usize)
+        }
+    }
+
+    // Removes the subject with the given id from the registry and returns it.
+    fn deregister_subject(&mut self, subject_id: u32) -> Option<&'a S> {
+        self.subjects
+            .iter()
+            .position(|x| x.id() == subject_id)
+            .map(|position| self.subjects.remove(position))
     }
 }
 
 impl<'a, S: Subject, G: Group> GroupRegistry for SimpleGroupRegistry<'a, S, G> {
     fn full(&self) -> bool {
-        self.subjects.len() as u32 >= self.capacity()
+        !self.has_unlimited_capacity() && self.subjects.len() >= self.capacity() as usize
+    }
+
+    fn number_of_members(&self) -> usize {
+        self.subjects.len()
     }
 
     fn subjects_ids_to_group_id(&self) -> HashMap<u32, u32> {
@@ -122,11 +195,84 @@ impl<'a, S: Subject, G: Group> GroupRegistry for SimpleGroupRegistry<'a, S, G> {
 
 impl<'a, S: Subject, G: Group> GrowingGroupRegistry<'a, S> for SimpleGroupRegistry<'a, S, G> {
     fn register_subject(&mut self, subject: &'a S) -> Result<(), CapacityError> {
-        if self.full() {
+        if !self.accepts(subject) {
             Err(CapacityError {})
         } else {
             self.subjects.push(subject);
             Ok(())
         }
     }
+
+    // The subject is accepted if the members and the subject can all be seated, each either in a reserved seat they are eligible for or in a general seat.
+    fn accepts(&self, subject: &S) -> bool {
+        if self.full() {
+            return false;
+        }
+        if self.reserved_seats_released || self.reserved_seats.is_empty() {
+            return true;
+        }
+        let members: Vec<&S> = self
+            .subjects
+            .iter()
+            .copied()
+            .chain(iter::once(subject))
+            .collect();
+        seats_suffice(
+            &members,
+            &self.reserved_seats,
+            self.general_seats() as usize,
+        )
+    }
+
+    fn release_reserved_seats(&mut self) {
+        self.reserved_seats_released = true;
+    }
+}
+
+/// Releases the reserved seats that were not taken and places the subjects that could not be placed before.
+/// Since the released seats may be in groups some already placed subjects prefer to their current group,
+/// subjects are (in the given order) moved to their most preferred group with room for them until no subject can improve.
+pub(super) fn release_reserved_seats<'a, S: Subject, G: Group>(
+    subjects: &'a [S],
+    mut group_registries: Vec<SimpleGroupRegistry<'a, S, G>>,
+    tie_breaker: &TieBreaker,
+) -> Vec<SimpleGroupRegistry<'a, S, G>> {
+    for group_registry in group_registries.iter_mut() {
+        group_registry.release_reserved_seats();
+    }
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for subject in subjects {
+            let current = group_registries
+                .iter()
+                .position(|x| x.subjects.iter().any(|y| y.id() == subject.id()));
+            let current_dissatisfaction =
+                current.map(|i| subject.dissatisfaction(&group_registries[i].id()));
+            let better = group_registries
+                .iter()
+                .enumerate()
+                .filter(|(_i, x)| x.accepts(subject))
+                .filter(|(_i, x)| {
+                    current_dissatisfaction.is_none_or(|d| subject.dissatisfaction(&x.id()) < d)
+                })
+                .min_by_key(|(_i, x)| {
+                    (
+                        subject.dissatisfaction(&x.id()),
+                        tie_breaker.key(subject.id(), x.id(), x.number_of_members()),
+                    )
+                })
+                .map(|(i, _x)| i);
+            if let Some(destination) = better {
+                if let Some(i) = current {
+                    group_registries[i].deregister_subject(subject.id());
+                }
+                group_registries[destination]
+                    .register_subject(subject)
+                    .unwrap();
+                improved = true;
+            }
+        }
+    }
+    group_registries
 }
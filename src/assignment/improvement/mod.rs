@@ -0,0 +1,354 @@
+//! # Improvement
+//! This module provides a local search that improves an existing [assignment](crate::Assignment), for instance the output of a fast assigner such as
+//! [FirstComeFirstServed](crate::assigners::FirstComeFirstServed).
+use super::Assignment;
+use crate::groups::Group;
+use crate::subjects::Subject;
+use std::time::{Duration, Instant};
+
+/// Decides which changes of an assignment count as improvements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ImprovementCriterion {
+    /// A change is an improvement if it reduces the total dissatisfaction, even if some of the subjects involved end up worse off.
+    #[default]
+    TotalDissatisfaction,
+    /// A change is an improvement if none of the subjects involved end up worse off and at least one of them is better off.
+    Pareto,
+}
+
+/// The outcome of a local search: the improved assignment together with some statistics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Improvement {
+    assignment: Assignment,
+    iterations: usize,
+    dissatisfaction_reduction: i64,
+    budget_exhausted: bool,
+}
+
+impl Improvement {
+    /// The improved assignment.
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+
+    /// The number of changes (moves, swaps and exchange cycles) that were applied.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// By how much the total dissatisfaction of the given subjects decreased.
+    pub fn dissatisfaction_reduction(&self) -> i64 {
+        self.dissatisfaction_reduction
+    }
+
+    /// Indicates whether the search was stopped by its budget. Otherwise no further improving change exists.
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget_exhausted
+    }
+}
+
+impl From<Improvement> for Assignment {
+    fn from(improvement: Improvement) -> Self {
+        improvement.assignment
+    }
+}
+
+/// Improves assignments by repeatedly applying the best available change among
+/// - moves of a single subject into a free seat of another group,
+/// - swaps of two subjects in different groups,
+/// - exchange cycles of three subjects in three different groups, each taking the seat of the next.
+///
+/// Only assigned subjects among the given subjects take part, and only the given groups are considered.
+/// Without a time limit the search is deterministic.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::{Assigner, FirstComeFirstServed};
+/// use group_assignment::improvement::LocalSearch;
+/// let subjects = [
+///     DefaultSubject::new(1, [(101, 0), (102, 1)].iter().cloned().collect(), 2),
+///     DefaultSubject::new(2, [(101, 0), (102, 5)].iter().cloned().collect(), 6),
+/// ];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+/// let improvement = LocalSearch::new().with_max_iterations(100).improve(&assignment, &subjects, &groups);
+/// // Swapping the two subjects reduces the total dissatisfaction from 5 to 1
+/// assert_eq!(4, improvement.dissatisfaction_reduction());
+/// assert_eq!(Some(&101), improvement.assignment().subject_to_group_id(&subjects[1]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalSearch {
+    criterion: ImprovementCriterion,
+    max_iterations: usize,
+    time_limit: Option<Duration>,
+}
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalSearch {
+    /// A local search reducing the total dissatisfaction with a budget of 10000 iterations and no time limit.
+    pub fn new() -> Self {
+        Self {
+            criterion: ImprovementCriterion::default(),
+            max_iterations: 10_000,
+            time_limit: None,
+        }
+    }
+
+    /// Sets the criterion deciding which changes are applied.
+    pub fn with_criterion(mut self, criterion: ImprovementCriterion) -> Self {
+        self.criterion = criterion;
+        self
+    }
+
+    /// Sets the maximal number of changes that are applied.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Stops the search once the given amount of time has passed. Note that the result then depends on the speed of the machine.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Improves the given assignment of the given subjects to the given groups.
+    pub fn improve<S: Subject, G: Group>(
+        &self,
+        assignment: &Assignment,
+        subjects: &[S],
+        groups: &[G],
+    ) -> Improvement {
+        let start = Instant::now();
+        let mut state = SearchState::new(assignment, subjects, groups);
+        let mut assignment = assignment.clone();
+        let (mut iterations, mut dissatisfaction_reduction) = (0, 0);
+        let budget_exhausted = loop {
+            if iterations == self.max_iterations
+                || self
+                    .time_limit
+                    .is_some_and(|limit| start.elapsed() >= limit)
+            {
+                break true;
+            }
+            let change = match state.best_change(self.criterion) {
+                Some(change) => change,
+                None => break false,
+            };
+            // Every subject takes the seat of the next one in the cycle (or the free seat for moves)
+            for (subject, to) in change.subjects.iter().zip(change.groups.iter()) {
+                state.place(*subject, *to);
+                assignment.unassign_subject(subjects[*subject].id());
+                assignment.assign_subject(subjects[*subject].id(), groups[*to].id());
+            }
+            iterations += 1;
+            dissatisfaction_reduction += change.gain;
+        };
+        Improvement {
+            assignment,
+            iterations,
+            dissatisfaction_reduction,
+            budget_exhausted,
+        }
+    }
+}
+
+// Changes are represented by the subjects involved together with their new groups.
+struct Change {
+    subjects: Vec<usize>,
+    groups: Vec<usize>,
+    gain: i64,
+}
+
+// Subjects and groups are referred to by their index in the input slices.
+struct SearchState {
+    dissatisfaction: Vec<Vec<i64>>,
+    placement: Vec<Option<usize>>,
+    sizes: Vec<usize>,
+    capacities: Vec<Option<usize>>,
+}
+
+impl SearchState {
+    fn new<S: Subject, G: Group>(assignment: &Assignment, subjects: &[S], groups: &[G]) -> Self {
+        Self {
+            dissatisfaction: subjects
+                .iter()
+                .map(|s| {
+                    groups
+                        .iter()
+                        .map(|g| s.dissatisfaction(&g.id()) as i64)
+                        .collect()
+                })
+                .collect(),
+            placement: subjects
+                .iter()
+                .map(|s| {
+                    assignment
+                        .subject_ids_to_group_ids
+                        .get(&s.id())
+                        .and_then(|id| groups.iter().position(|g| g.id() == *id))
+                })
+                .collect(),
+            // Members that are not among the given subjects still occupy seats
+            sizes: groups
+                .iter()
+                .map(|g| {
+                    assignment
+                        .group_ids_to_subjects_ids
+                        .get(&g.id())
+                        .map_or(0, |x| x.len())
+                })
+                .collect(),
+            capacities: groups
+                .iter()
+                .map(|g| (!g.has_unlimited_capacity()).then(|| g.capacity() as usize))
+                .collect(),
+        }
+    }
+
+    fn place(&mut self, subject: usize, to: usize) {
+        if let Some(from) = self.placement[subject] {
+            self.sizes[from] -= 1;
+        }
+        self.sizes[to] += 1;
+        self.placement[subject] = Some(to);
+    }
+
+    // The best change according to the criterion, where moves win ties against swaps, swaps against cycles and smaller indices against larger ones.
+    fn best_change(&self, criterion: ImprovementCriterion) -> Option<Change> {
+        let number_of_groups = self.sizes.len();
+        // best[a][b] is the subject in group a gaining the most from moving to group b, together with its gain
+        let mut best: Vec<Vec<Option<(usize, i64)>>> =
+            vec![vec![None; number_of_groups]; number_of_groups];
+        for (i, placement) in self.placement.iter().enumerate() {
+            if let Some(a) = *placement {
+                for b in (0..number_of_groups).filter(|b| *b != a) {
+                    let gain = self.dissatisfaction[i][a] - self.dissatisfaction[i][b];
+                    if best[a][b].is_none_or(|(_, best_gain)| gain > best_gain) {
+                        best[a][b] = Some((i, gain));
+                    }
+                }
+            }
+        }
+        let mut best_change: Option<Change> = None;
+        // Considers the change where, for every step (a, b), the best subject of group a moves to group b
+        let mut consider = |steps: &[(usize, usize)]| {
+            let participants: Option<Vec<(usize, i64)>> =
+                steps.iter().map(|(a, b)| best[*a][*b]).collect();
+            let participants = match participants {
+                Some(participants) => participants,
+                None => return,
+            };
+            let gain: i64 = participants.iter().map(|(_, gain)| *gain).sum();
+            let acceptable = match criterion {
+                ImprovementCriterion::TotalDissatisfaction => gain > 0,
+                ImprovementCriterion::Pareto => {
+                    gain > 0 && participants.iter().all(|(_, gain)| *gain >= 0)
+                }
+            };
+            if acceptable && best_change.as_ref().is_none_or(|x| gain > x.gain) {
+                best_change = Some(Change {
+                    subjects: participants.iter().map(|(i, _)| *i).collect(),
+                    groups: steps.iter().map(|(_, b)| *b).collect(),
+                    gain,
+                });
+            }
+        };
+        for a in 0..number_of_groups {
+            for b in 0..number_of_groups {
+                if b != a && self.capacities[b].is_none_or(|c| self.sizes[b] < c) {
+                    consider(&[(a, b)]);
+                }
+            }
+        }
+        for a in 0..number_of_groups {
+            for b in (a + 1)..number_of_groups {
+                consider(&[(a, b), (b, a)]);
+            }
+        }
+        // Every cycle of three groups is considered in both directions, starting from its smallest group
+        for a in 0..number_of_groups {
+            for b in (a + 1)..number_of_groups {
+                for c in ((a + 1)..number_of_groups).filter(|c| *c != b) {
+                    consider(&[(a, b), (b, c), (c, a)]);
+                }
+            }
+        }
+        best_change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::test_utils::assignment;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn improve_exchange_cycle() {
+        let group_ids = [101_u32, 102, 103];
+        // Every subject wants the group of the next subject, but no two subjects want to swap
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[1], group_ids[0], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[2], group_ids[1], group_ids[0]]),
+            TestSubject::new(3, vec![group_ids[0], group_ids[2], group_ids[1]]),
+        ];
+        let groups: Vec<DefaultGroup> = group_ids
+            .iter()
+            .map(|id| DefaultGroup::new(*id, 1))
+            .collect();
+        let initial = assignment(&[(1, group_ids[0]), (2, group_ids[1]), (3, group_ids[2])]);
+        let improvement = LocalSearch::new()
+            .with_criterion(ImprovementCriterion::Pareto)
+            .improve(&initial, &subjects, &groups);
+        assert_eq!(1, improvement.iterations());
+        assert_eq!(3, improvement.dissatisfaction_reduction());
+        assert!(!improvement.budget_exhausted());
+        assert_eq!(
+            assignment(&[(1, group_ids[1]), (2, group_ids[2]), (3, group_ids[0])]),
+            improvement.into()
+        );
+    }
+
+    #[test]
+    fn improve_criteria_and_budget() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[2], group_ids[1]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 0),
+        ];
+        let initial = assignment(&[(1, group_ids[0]), (2, group_ids[1])]);
+        // Swapping costs the first subject one and gains the second subject two
+        let total = LocalSearch::new().improve(&initial, &subjects, &groups);
+        assert_eq!(
+            (1, 1),
+            (total.iterations(), total.dissatisfaction_reduction())
+        );
+        assert_eq!(
+            Some(&group_ids[0]),
+            total.assignment().subject_to_group_id(&subjects[1])
+        );
+        let pareto = LocalSearch::new()
+            .with_criterion(ImprovementCriterion::Pareto)
+            .improve(&initial, &subjects, &groups);
+        assert_eq!(0, pareto.iterations());
+        assert_eq!(&initial, pareto.assignment());
+        let exhausted = LocalSearch::new()
+            .with_max_iterations(0)
+            .improve(&initial, &subjects, &groups);
+        assert!(exhausted.budget_exhausted());
+        assert_eq!(&initial, exhausted.assignment());
+    }
+}
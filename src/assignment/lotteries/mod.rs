@@ -0,0 +1,430 @@
+//! # Lotteries
+//! This module provides random assignments: rather than a single [assignment](crate::Assignment), every subject receives a probability of ending up in each group.
+//!
+//! Random assignments are computed with the [Probabilistic Serial](ProbabilisticSerial) mechanism and can be turned into a [lottery](Lottery)
+//! over assignments, from which outcomes are drawn reproducibly by seed.
+use super::errors::{self, AssignmentError};
+use super::min_cost_flow::MinCostFlow;
+use super::random::Rng;
+use super::tie_breaking::{TieBreaker, TieBreaking};
+use super::Assignment;
+use crate::groups::{effective_capacity, Group};
+use crate::subjects::Subject;
+use std::collections::HashMap;
+
+// Probabilities closer than this are considered equal.
+const EPSILON: f64 = 1e-9;
+
+/// Computes random assignments with the Probabilistic Serial mechanism of Bogomolnaia and Moulin.
+///
+/// Every seat of a group is a unit of probability. All subjects simultaneously and at the same speed "eat" probability of their most preferred group
+/// (the one they are least dissatisfied with) that has some left, moving on to their next preferred group once it is used up, until every subject has eaten
+/// a probability of one. Groups a subject is equally dissatisfied with are eaten in the order given by a [tie-breaking policy](TieBreaking).
+///
+/// The result is envy-free in expectation: no subject would rather have the probabilities of another subject.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::lotteries::ProbabilisticSerial;
+/// let prefer_first = |id| DefaultSubject::new(id, [(101, 0), (102, 1)].iter().cloned().collect(), 2);
+/// let subjects = [prefer_first(1), prefer_first(2)];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// let random_assignment = ProbabilisticSerial::random_assignment(&subjects, &groups).unwrap();
+/// assert_eq!(0.5, random_assignment.probability(1, 101));
+/// // The lottery consists of the two ways of assigning the subjects, each drawn with probability one half
+/// let lottery = random_assignment.lottery().unwrap();
+/// assert_eq!(2, lottery.outcomes().len());
+/// // Equal seeds draw equal outcomes
+/// let drawn = lottery.sample(7).subject_to_group_id(&subjects[0]);
+/// assert_eq!(drawn, lottery.sample(7).subject_to_group_id(&subjects[0]));
+/// ```
+pub struct ProbabilisticSerial {}
+
+impl ProbabilisticSerial {
+    /// The random assignment of the given subjects to the given groups, breaking ties in the [default order](TieBreaking::GroupOrder).
+    pub fn random_assignment<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+    ) -> Result<RandomAssignment, AssignmentError> {
+        Self::random_assignment_with_tie_breaking(subjects, groups, TieBreaking::default())
+    }
+
+    /// The random assignment of the given subjects to the given groups, where subjects eat from groups they are equally dissatisfied with
+    /// in the order given by the [tie-breaking policy](TieBreaking). Policies depending on the number of members rank groups as if they were empty.
+    pub fn random_assignment_with_tie_breaking<S: Subject, G: Group>(
+        subjects: &[S],
+        groups: &[G],
+        tie_breaking: TieBreaking,
+    ) -> Result<RandomAssignment, AssignmentError> {
+        errors::unique_ids(
+            subjects.iter().map(|s| s.id()),
+            groups.iter().map(|g| g.id()),
+        )?;
+        errors::sufficient_capacity(subjects.len(), groups)?;
+        errors::no_reserved_seats(groups)?;
+        let tie_breaker = TieBreaker::new(tie_breaking, groups);
+        let seats: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()).min(subjects.len()))
+            .collect();
+        // The groups of every subject from most to least preferred
+        let orders: Vec<Vec<usize>> = subjects
+            .iter()
+            .map(|s| {
+                let ranks = tie_breaker.ranks(s.id(), groups);
+                let mut order: Vec<usize> = (0..groups.len()).collect();
+                order.sort_by_key(|j| (s.dissatisfaction(&groups[*j].id()), ranks[*j]));
+                order
+            })
+            .collect();
+        let mut supply: Vec<f64> = seats.iter().map(|x| *x as f64).collect();
+        let mut probabilities = vec![vec![0.0; groups.len()]; subjects.len()];
+        let mut time = 0.0;
+        while time < 1.0 - EPSILON {
+            let eating: Vec<Option<usize>> = orders
+                .iter()
+                .map(|order| order.iter().copied().find(|j| supply[*j] > EPSILON))
+                .collect();
+            let mut eaters = vec![0_usize; groups.len()];
+            for j in eating.iter().flatten() {
+                eaters[*j] += 1;
+            }
+            // Eat until the next group is used up or every subject is done
+            let step = (0..groups.len())
+                .filter(|j| eaters[*j] > 0)
+                .map(|j| supply[j] / eaters[j] as f64)
+                .fold(1.0 - time, f64::min);
+            if eating.iter().all(|j| j.is_none()) {
+                break;
+            }
+            for (i, j) in eating.iter().enumerate() {
+                if let Some(j) = j {
+                    probabilities[i][*j] += step;
+                }
+            }
+            for (j, eaters) in eaters.iter().enumerate() {
+                supply[j] = (supply[j] - step * *eaters as f64).max(0.0);
+            }
+            time += step;
+        }
+        Ok(RandomAssignment {
+            subject_ids: subjects.iter().map(|s| s.id()).collect(),
+            group_ids: groups.iter().map(|g| g.id()).collect(),
+            seats,
+            probabilities,
+        })
+    }
+}
+
+/// The probabilities of every subject ending up in every group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomAssignment {
+    subject_ids: Vec<u32>,
+    group_ids: Vec<u32>,
+    seats: Vec<usize>,
+    probabilities: Vec<Vec<f64>>,
+}
+
+impl RandomAssignment {
+    /// The ids of the subjects, in the order of the rows of the [probabilities](RandomAssignment::probabilities).
+    pub fn subject_ids(&self) -> &Vec<u32> {
+        &self.subject_ids
+    }
+
+    /// The ids of the groups, in the order of the columns of the [probabilities](RandomAssignment::probabilities).
+    pub fn group_ids(&self) -> &Vec<u32> {
+        &self.group_ids
+    }
+
+    /// The matrix of probabilities with one row per subject and one column per group. Every row sums to one.
+    pub fn probabilities(&self) -> &Vec<Vec<f64>> {
+        &self.probabilities
+    }
+
+    /// The probability of the subject with the given id ending up in the group with the given id, which is zero for unknown ids.
+    pub fn probability(&self, subject_id: u32, group_id: u32) -> f64 {
+        match (
+            self.subject_ids.iter().position(|x| *x == subject_id),
+            self.group_ids.iter().position(|x| *x == group_id),
+        ) {
+            (Some(i), Some(j)) => self.probabilities[i][j],
+            _ => 0.0,
+        }
+    }
+
+    /// Decomposes the random assignment into a lottery over assignments, such that every subject ends up in every group with the given probability.
+    ///
+    /// The decomposition follows Birkhoff and von Neumann: repeatedly an assignment is chosen among the pairs of subjects and groups with a positive remaining
+    /// probability, and as much probability as possible is shifted onto it. The number of members of every group in every outcome is its expected number
+    /// of members rounded up or down.
+    ///
+    /// An [Infeasible](AssignmentError::Infeasible) error is returned if rounding errors prevent the decomposition from accounting for all of the probability.
+    pub fn lottery(&self) -> Result<Lottery, AssignmentError> {
+        let (number_of_subjects, number_of_groups) = (self.subject_ids.len(), self.group_ids.len());
+        let mut remaining = self.probabilities.clone();
+        let mut weight = 1.0;
+        let mut outcomes: Vec<(f64, Assignment)> = Vec::new();
+        // Without subjects the empty assignment is certain
+        if number_of_subjects == 0 {
+            outcomes.push((1.0, self.assignment(&[])));
+            return Ok(Lottery { outcomes });
+        }
+        // Every step removes a pair of subject and group from the support or makes a group full in all remaining outcomes
+        for _ in 0..=(number_of_subjects + 1) * (number_of_groups + 1) {
+            if weight <= EPSILON {
+                break;
+            }
+            let expected_sizes: Vec<f64> = (0..number_of_groups)
+                .map(|j| remaining.iter().map(|row| row[j]).sum::<f64>() / weight)
+                .collect();
+            let placement = self
+                .rounded_placement(&remaining, &expected_sizes)
+                .ok_or_else(|| AssignmentError::Infeasible {
+                    reason: "No assignment fits the remaining probabilities".to_string(),
+                })?;
+            let mut sizes = vec![0_usize; number_of_groups];
+            for j in placement.iter() {
+                sizes[*j] += 1;
+            }
+            let step = placement
+                .iter()
+                .enumerate()
+                .map(|(i, j)| remaining[i][*j])
+                .chain(
+                    (0..number_of_groups)
+                        .filter(|j| sizes[*j] < self.seats[*j])
+                        .map(|j| {
+                            (self.seats[j] as f64 - expected_sizes[j]) * weight
+                                / (self.seats[j] - sizes[j]) as f64
+                        }),
+                )
+                .fold(weight, f64::min);
+            for (i, j) in placement.iter().enumerate() {
+                remaining[i][*j] -= step;
+            }
+            for x in remaining.iter_mut().flatten() {
+                if *x < EPSILON {
+                    *x = 0.0;
+                }
+            }
+            weight -= step;
+            outcomes.push((step, self.assignment(&placement)));
+        }
+        if weight > EPSILON {
+            return Err(AssignmentError::Infeasible {
+                reason: format!("A probability of {} is left after every step", weight),
+            });
+        }
+        // Rounding errors must not make the probabilities of the outcomes sum to anything but one
+        let total: f64 = outcomes.iter().map(|(p, _)| p).sum();
+        for (p, _) in outcomes.iter_mut() {
+            *p /= total;
+        }
+        Ok(Lottery { outcomes })
+    }
+
+    // Places every subject in a group it has a positive remaining probability for, such that the size of every group is its expected size rounded up or down.
+    fn rounded_placement(
+        &self,
+        remaining: &[Vec<f64>],
+        expected_sizes: &[f64],
+    ) -> Option<Vec<usize>> {
+        let (number_of_subjects, number_of_groups) = (self.subject_ids.len(), self.group_ids.len());
+        let source = number_of_subjects + number_of_groups;
+        let sink = source + 1;
+        let mut network: MinCostFlow = MinCostFlow::new(sink + 1);
+        let mut subject_edges: Vec<Vec<(usize, usize)>> = Vec::with_capacity(number_of_subjects);
+        for (i, row) in remaining.iter().enumerate() {
+            network.add_edge(source, i, 1, 0);
+            subject_edges.push(
+                (0..number_of_groups)
+                    .filter(|j| row[*j] > 0.0)
+                    .map(|j| (j, network.add_edge(i, number_of_subjects + j, 1, 0)))
+                    .collect(),
+            );
+        }
+        let mut required = 0;
+        for (j, expected_size) in expected_sizes.iter().enumerate() {
+            let rounded_down = ((expected_size + EPSILON).floor() as usize).min(self.seats[j]);
+            let rounded_up =
+                ((expected_size - EPSILON).ceil() as usize).clamp(rounded_down, self.seats[j]);
+            // The seats up to the rounded down size must be taken, which the negative costs ensure
+            network.add_edge(number_of_subjects + j, sink, rounded_down as i64, -1);
+            network.add_edge(
+                number_of_subjects + j,
+                sink,
+                (rounded_up - rounded_down) as i64,
+                0,
+            );
+            required += rounded_down as i128;
+        }
+        let (placed, cost) = network.run(source, sink, number_of_subjects as i64);
+        if placed < number_of_subjects as i64 || cost > -required {
+            return None;
+        }
+        subject_edges
+            .iter()
+            .map(|edges| {
+                edges
+                    .iter()
+                    .find(|(_, edge)| network.flow(*edge) > 0)
+                    .map(|(j, _)| *j)
+            })
+            .collect()
+    }
+
+    fn assignment(&self, placement: &[usize]) -> Assignment {
+        let mut group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> =
+            self.group_ids.iter().map(|id| (*id, Vec::new())).collect();
+        let mut subject_ids_to_group_ids = HashMap::new();
+        for (subject_id, j) in self.subject_ids.iter().zip(placement.iter()) {
+            subject_ids_to_group_ids.insert(*subject_id, self.group_ids[*j]);
+            group_ids_to_subjects_ids
+                .entry(self.group_ids[*j])
+                .or_default()
+                .push(*subject_id);
+        }
+        Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids))
+    }
+}
+
+/// A probability distribution over assignments.
+#[derive(Debug, Clone)]
+pub struct Lottery {
+    outcomes: Vec<(f64, Assignment)>,
+}
+
+impl Lottery {
+    /// The possible assignments together with their probabilities, which sum to one.
+    pub fn outcomes(&self) -> &Vec<(f64, Assignment)> {
+        &self.outcomes
+    }
+
+    /// Draws an assignment. Equal seeds give equal assignments.
+    pub fn sample(&self, seed: u64) -> &Assignment {
+        let mut draw = Rng::new(seed).next_f64();
+        for (probability, assignment) in self.outcomes.iter() {
+            if draw < *probability {
+                return assignment;
+            }
+            draw -= probability;
+        }
+        &self
+            .outcomes
+            .last()
+            .expect("every lottery has an outcome")
+            .1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn random_assignment_eats_in_order() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[2], group_ids[1]]),
+            TestSubject::new(3, vec![group_ids[1], group_ids[0], group_ids[2]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 2),
+        ];
+        let random_assignment = ProbabilisticSerial::random_assignment(&subjects, &groups).unwrap();
+        // The first two subjects share the first group while the third subject eats half of the second group.
+        // Then the first and the third subject share the rest of the second group, and everyone finishes on the last group.
+        let expected = [[0.5, 0.25, 0.25], [0.5, 0.0, 0.5], [0.0, 0.75, 0.25]];
+        for (row, expected_row) in random_assignment
+            .probabilities()
+            .iter()
+            .zip(expected.iter())
+        {
+            for (p, q) in row.iter().zip(expected_row.iter()) {
+                assert!((p - q).abs() < 1e-9);
+            }
+        }
+    }
+
+    // Checks that the lottery reproduces the probabilities of the random assignment with valid outcomes, and returns the number of outcomes.
+    fn check_lottery(subjects: &[TestSubject], groups: &[DefaultGroup]) -> usize {
+        let random_assignment = ProbabilisticSerial::random_assignment(subjects, groups).unwrap();
+        let lottery = random_assignment.lottery().unwrap();
+        let total: f64 = lottery.outcomes().iter().map(|(p, _)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for subject in subjects.iter() {
+            for group in groups.iter() {
+                let in_lottery: f64 = lottery
+                    .outcomes()
+                    .iter()
+                    .filter(|(_, a)| a.subject_to_group_id(subject) == Some(&group.id()))
+                    .map(|(p, _)| p)
+                    .sum();
+                assert!(
+                    (in_lottery - random_assignment.probability(subject.id(), group.id())).abs()
+                        < 1e-9
+                );
+            }
+        }
+        for (_, assignment) in lottery.outcomes() {
+            assert!(assignment.validate(subjects, groups).is_empty());
+        }
+        lottery.outcomes().len()
+    }
+
+    #[test]
+    fn lottery_without_subjects() {
+        let subjects: [TestSubject; 0] = [];
+        let groups = [DefaultGroup::new(101, 1)];
+        let lottery = ProbabilisticSerial::random_assignment(&subjects, &groups)
+            .unwrap()
+            .lottery()
+            .unwrap();
+        assert_eq!(1, lottery.outcomes().len());
+        assert_eq!(0, lottery.sample(7).number_of_assigned_subjects());
+    }
+
+    #[test]
+    fn lottery_reproduces_probabilities() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[1], group_ids[2]]),
+            TestSubject::new(3, vec![group_ids[1], group_ids[0], group_ids[2]]),
+            TestSubject::new(4, vec![group_ids[2], group_ids[1], group_ids[0]]),
+            TestSubject::new(5, vec![group_ids[0], group_ids[2], group_ids[1]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 2),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 3),
+        ];
+        // Unequal capacities and overlapping preferences require more than two outcomes
+        assert!(check_lottery(&subjects, &groups) > 2);
+    }
+
+    #[test]
+    fn lottery_reproduces_probabilities_with_spare_seats() {
+        let group_ids = [101_u32, 102, 103, 104];
+        // Every subject ranks the groups in a different rotation
+        let subjects: Vec<TestSubject> = (0..8)
+            .map(|id| {
+                let mut preferences = group_ids.to_vec();
+                preferences.rotate_left(id as usize % 3);
+                TestSubject::new(id, preferences)
+            })
+            .collect();
+        let groups = [
+            DefaultGroup::new(group_ids[0], 3),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 2),
+            DefaultGroup::new(group_ids[3], 4),
+        ];
+        assert!(check_lottery(&subjects, &groups) > 2);
+    }
+}
@@ -0,0 +1,379 @@
+//! # Minimum cost flows
+//! Crate private helper module for assigners that optimise an objective over all subjects at once.
+//!
+//! Most such assigners can be phrased as a transportation problem: every subject must be shipped to exactly one group,
+//! every group can receive at most its capacity and shipping a subject to a group has a cost (typically the subject's dissatisfaction).
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Edge costs of a flow network. Costs are only ever added, negated, multiplied by amounts of flow and compared,
+/// hence besides integers also [lexicographically ordered vectors](LexicographicCost) can be used.
+pub(crate) trait Cost: Clone + Ord {
+    fn zero() -> Self;
+    fn plus(&self, other: &Self) -> Self;
+    fn negated(&self) -> Self;
+    fn times(&self, factor: i64) -> Self;
+}
+
+impl Cost for i128 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn plus(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn negated(&self) -> Self {
+        -self
+    }
+
+    fn times(&self, factor: i64) -> Self {
+        self * factor as i128
+    }
+}
+
+/// A vector of integers compared lexicographically, where missing trailing entries count as zero.
+/// Minimising such costs optimises the first entry, then the second one and so on, without the risk of overflows that comes with scaling.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LexicographicCost(pub(crate) Vec<i64>);
+
+impl LexicographicCost {
+    fn entry(&self, index: usize) -> i64 {
+        self.0.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl PartialEq for LexicographicCost {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for LexicographicCost {}
+
+impl PartialOrd for LexicographicCost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LexicographicCost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (0..self.0.len().max(other.0.len()))
+            .map(|k| self.entry(k).cmp(&other.entry(k)))
+            .find(|x| *x != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Cost for LexicographicCost {
+    fn zero() -> Self {
+        Self(Vec::new())
+    }
+
+    fn plus(&self, other: &Self) -> Self {
+        Self(
+            (0..self.0.len().max(other.0.len()))
+                .map(|k| self.entry(k) + other.entry(k))
+                .collect(),
+        )
+    }
+
+    fn negated(&self) -> Self {
+        Self(self.0.iter().map(|x| -x).collect())
+    }
+
+    fn times(&self, factor: i64) -> Self {
+        Self(self.0.iter().map(|x| x * factor).collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Edge<C> {
+    to: usize,
+    capacity: i64,
+    cost: C,
+}
+
+/// A flow network solved with the successive shortest path method.
+/// Shortest paths are found with a queue based Bellman-Ford search so negative edge costs are allowed
+/// as long as the network does not contain negative cycles.
+#[derive(Debug, Clone)]
+pub(crate) struct MinCostFlow<C = i128> {
+    // Edges are stored in pairs: the edge with index e has its residual counterpart at index e ^ 1.
+    edges: Vec<Edge<C>>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl<C: Cost> MinCostFlow<C> {
+    pub(crate) fn new(number_of_nodes: usize) -> Self {
+        Self {
+            edges: Vec::new(),
+            adjacency: vec![Vec::new(); number_of_nodes],
+        }
+    }
+
+    /// Adds a directed edge and returns its index which can later be passed to [flow](MinCostFlow::flow).
+    pub(crate) fn add_edge(&mut self, from: usize, to: usize, capacity: i64, cost: C) -> usize {
+        let index = self.edges.len();
+        let residual = Edge {
+            to: from,
+            capacity: 0,
+            cost: cost.negated(),
+        };
+        self.edges.push(Edge { to, capacity, cost });
+        self.edges.push(residual);
+        self.adjacency[from].push(index);
+        self.adjacency[to].push(index + 1);
+        index
+    }
+
+    /// The amount of flow currently passing through the given edge.
+    pub(crate) fn flow(&self, edge: usize) -> i64 {
+        self.edges[edge ^ 1].capacity
+    }
+
+    /// Sends up to `max_flow` units from the source to the sink as cheaply as possible.
+    /// Returns the amount of flow that was sent together with its total cost.
+    pub(crate) fn run(&mut self, source: usize, sink: usize, max_flow: i64) -> (i64, C) {
+        let (mut total_flow, mut total_cost) = (0_i64, C::zero());
+        while total_flow < max_flow {
+            let predecessors = match self.shortest_path(source, sink) {
+                Some(predecessors) => predecessors,
+                None => break,
+            };
+            let mut bottleneck = max_flow - total_flow;
+            let mut node = sink;
+            while node != source {
+                let edge = predecessors[node].unwrap();
+                bottleneck = bottleneck.min(self.edges[edge].capacity);
+                node = self.edges[edge ^ 1].to;
+            }
+            let mut node = sink;
+            while node != source {
+                let edge = predecessors[node].unwrap();
+                self.edges[edge].capacity -= bottleneck;
+                self.edges[edge ^ 1].capacity += bottleneck;
+                total_cost = total_cost.plus(&self.edges[edge].cost.times(bottleneck));
+                node = self.edges[edge ^ 1].to;
+            }
+            total_flow += bottleneck;
+        }
+        (total_flow, total_cost)
+    }
+
+    // Returns for every node the edge used to reach it on a cheapest residual path from the source,
+    // or None if the sink can not be reached.
+    fn shortest_path(&self, source: usize, sink: usize) -> Option<Vec<Option<usize>>> {
+        let number_of_nodes = self.adjacency.len();
+        let mut distances: Vec<Option<C>> = vec![None; number_of_nodes];
+        let mut predecessors: Vec<Option<usize>> = vec![None; number_of_nodes];
+        let mut queued = vec![false; number_of_nodes];
+        let mut queue = VecDeque::new();
+        distances[source] = Some(C::zero());
+        queue.push_back(source);
+        queued[source] = true;
+        while let Some(node) = queue.pop_front() {
+            queued[node] = false;
+            let distance = distances[node].clone().unwrap();
+            for &edge in self.adjacency[node].iter() {
+                let Edge { to, capacity, cost } = &self.edges[edge];
+                let candidate = distance.plus(cost);
+                if *capacity > 0 && distances[*to].as_ref().is_none_or(|d| candidate < *d) {
+                    let to = *to;
+                    distances[to] = Some(candidate);
+                    predecessors[to] = Some(edge);
+                    if !queued[to] {
+                        queued[to] = true;
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        distances[sink].as_ref().map(|_| predecessors)
+    }
+}
+
+/// Solves the transportation problem of assigning every subject to a group.
+///
+/// `cost(i, j)` is the cost (of any integer type fitting into an i128) of assigning the i'th subject to the j'th group, or None if this is forbidden.
+/// The returned vector contains the index of the group of every subject, or None for subjects that could not be placed.
+/// Among all ways of placing as many subjects as possible, one of minimal total cost is returned, and among those one of minimal total tie cost.
+///
+/// `tie_cost(i, j)` is the non-negative tie cost of assigning the i'th subject to the j'th group.
+/// If `seat_cost` is given, `seat_cost(j, k)` is the non-negative cost of the k'th member (counting from 0) of the j'th group, which must not decrease in k.
+/// Seat costs are minimised after the costs and before the tie costs, so that subjects can be spread over equally good groups.
+pub(crate) fn cheapest_placement<F, C, T>(
+    number_of_subjects: usize,
+    capacities: &[usize],
+    cost: F,
+    tie_cost: T,
+    seat_cost: Option<&dyn Fn(usize, usize) -> i64>,
+) -> Vec<Option<usize>>
+where
+    F: Fn(usize, usize) -> Option<C>,
+    C: Into<i128>,
+    T: Fn(usize, usize) -> i64,
+{
+    let number_of_groups = capacities.len();
+    let costs: Vec<Vec<Option<(i128, i64)>>> = (0..number_of_subjects)
+        .map(|i| {
+            (0..number_of_groups)
+                .map(|j| cost(i, j).map(|c| (c.into(), tie_cost(i, j))))
+                .collect()
+        })
+        .collect();
+    // Scaling the costs by more than the largest possible total tie cost makes the tie costs a secondary objective
+    let largest_tie_cost = costs
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|(_c, t)| *t as i128)
+        .max()
+        .unwrap_or(0);
+    let seats: Vec<usize> = capacities
+        .iter()
+        .map(|c| (*c).min(number_of_subjects))
+        .collect();
+    let largest_seat_cost = seat_cost.map_or(0, |seat_cost| {
+        seats
+            .iter()
+            .enumerate()
+            .filter(|(_j, seats)| **seats > 0)
+            .map(|(j, seats)| seat_cost(j, seats - 1) as i128)
+            .max()
+            .unwrap_or(0)
+    });
+    let n = number_of_subjects as i128;
+    let seat_scale = n * largest_tie_cost + 1;
+    let scale = seat_scale * (n * largest_seat_cost + 1);
+
+    let source = number_of_subjects + number_of_groups;
+    let sink = source + 1;
+    let mut network: MinCostFlow = MinCostFlow::new(sink + 1);
+    let mut subject_edges: Vec<Vec<(usize, usize)>> = Vec::with_capacity(number_of_subjects);
+    for (i, subject_costs) in costs.iter().enumerate() {
+        network.add_edge(source, i, 1, 0);
+        subject_edges.push(
+            subject_costs
+                .iter()
+                .enumerate()
+                .filter_map(|(j, c)| {
+                    c.map(|(c, t)| {
+                        let edge =
+                            network.add_edge(i, number_of_subjects + j, 1, c * scale + t as i128);
+                        (j, edge)
+                    })
+                })
+                .collect(),
+        );
+    }
+    for (j, seats) in seats.iter().enumerate() {
+        match seat_cost {
+            Some(seat_cost) => {
+                for k in 0..*seats {
+                    network.add_edge(
+                        number_of_subjects + j,
+                        sink,
+                        1,
+                        seat_cost(j, k) as i128 * seat_scale,
+                    );
+                }
+            }
+            None => {
+                network.add_edge(number_of_subjects + j, sink, *seats as i64, 0);
+            }
+        }
+    }
+    network.run(source, sink, number_of_subjects as i64);
+    subject_edges
+        .iter()
+        .map(|edges| {
+            edges
+                .iter()
+                .find(|(_j, edge)| network.flow(*edge) > 0)
+                .map(|(j, _edge)| *j)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cheapest_placement_prefers_lower_total_cost() {
+        // Greedily giving the first subject its favourite would force the second subject into a very expensive group.
+        let costs = [[0_i64, 1], [0, 10]];
+        let placement = cheapest_placement(2, &[1, 1], |i, j| Some(costs[i][j]), |_i, _j| 0, None);
+        assert_eq!(placement, vec![Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn cheapest_placement_respects_forbidden_pairs() {
+        let placement = cheapest_placement(
+            3,
+            &[2, 2],
+            |i, j| {
+                if i == 0 && j == 0 {
+                    None
+                } else {
+                    Some(0)
+                }
+            },
+            |_i, _j| 0,
+            None,
+        );
+        assert_eq!(placement[0], Some(1));
+        assert!(placement.iter().all(|x| x.is_some()));
+    }
+
+    #[test]
+    fn cheapest_placement_insufficient_capacity() {
+        let placement = cheapest_placement(3, &[1, 1], |_i, _j| Some(0), |_i, _j| 0, None);
+        assert_eq!(placement.iter().filter(|x| x.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn lexicographic_costs() {
+        let cost = |x: &[i64]| LexicographicCost(x.to_vec());
+        assert!(cost(&[1, -1]) < cost(&[1]));
+        assert!(cost(&[0, 1]) > cost(&[]));
+        assert_eq!(cost(&[2, 0]), cost(&[1, 5]).plus(&cost(&[1, -5])));
+        // A single unit of the first entry outweighs any amount of the second entry
+        let mut network: MinCostFlow<LexicographicCost> = MinCostFlow::new(4);
+        let cheap_first = network.add_edge(0, 1, 1, cost(&[0, 1_000_000]));
+        network.add_edge(0, 2, 1, cost(&[1, 0]));
+        network.add_edge(1, 3, 1, cost(&[]));
+        network.add_edge(2, 3, 1, cost(&[]));
+        assert_eq!((1, cost(&[0, 1_000_000])), network.run(0, 3, 1));
+        assert_eq!(1, network.flow(cheap_first));
+    }
+
+    #[test]
+    fn cheapest_placement_breaks_ties() {
+        let members = |_j: usize, k: usize| k as i64;
+        // Both subjects are indifferent, the tie costs favour the second group for both
+        let placement =
+            cheapest_placement(2, &[2, 2], |_i, _j| Some(0), |_i, j| 1 - j as i64, None);
+        assert_eq!(placement, vec![Some(1), Some(1)]);
+        let placement = cheapest_placement(
+            2,
+            &[2, 2],
+            |_i, _j| Some(0),
+            |_i, j| 1 - j as i64,
+            Some(&members),
+        );
+        assert_eq!(placement.iter().filter(|x| **x == Some(1)).count(), 1);
+        // Tie costs never outweigh actual costs
+        let placement = cheapest_placement(
+            1,
+            &[1, 1],
+            |_i, j| Some(j as i64),
+            |_i, j| 1 - j as i64,
+            Some(&members),
+        );
+        assert_eq!(placement, vec![Some(0)]);
+    }
+}
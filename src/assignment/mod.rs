@@ -1,13 +1,33 @@
 use std::collections::HashMap;
 
+use crate::groups::QuotaViolation;
 use crate::{Group, Subject};
 pub mod assigners;
+mod balancing;
+pub mod comparison;
+pub mod efficiency;
+pub mod envy;
 pub mod errors;
 mod group_management;
+pub mod improvement;
+pub mod lotteries;
+mod min_cost_flow;
+pub mod pairing;
+mod random;
+pub mod simulation;
+pub mod teams;
+#[cfg(test)]
+pub(crate) mod test_utils;
+mod tie_breaking;
+pub mod validation;
+pub mod waitlists;
 
 /// Describes relationships between subjects and groups
 ///
 /// Assignments are typically obtained from an [Assigner](assigners::Assigner)
+///
+/// Two assignments are equal if they assign the same subjects to the same groups, regardless of the order in which the members of a group are listed.
+#[derive(Debug, Clone)]
 pub struct Assignment {
     subject_ids_to_group_ids: HashMap<u32, u32>,
     group_ids_to_subjects_ids: HashMap<u32, Vec<u32>>,
@@ -21,8 +41,143 @@ impl Assignment {
     pub fn group_to_subjects_ids<G: Group>(&self, group: &G) -> Option<&Vec<u32>> {
         self.group_ids_to_subjects_ids.get(&group.id())
     }
+
+    /// Get the id of the group the subject with the given id is assigned to.
+    pub fn subject_id_to_group_id(&self, subject_id: &u32) -> Option<&u32> {
+        self.subject_ids_to_group_ids.get(subject_id)
+    }
+
+    /// Get the ids of the subjects assigned to the group with the given id.
+    pub fn group_id_to_subjects_ids(&self, group_id: &u32) -> Option<&Vec<u32>> {
+        self.group_ids_to_subjects_ids.get(group_id)
+    }
+
+    /// An iterator over the pairs (subject id, group id) of every assigned subject, in arbitrary order.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, DefaultSubject};
+    /// use group_assignment::assigners::{Assigner, FirstComeFirstServed};
+    /// use std::collections::HashMap;
+    /// let subjects = [DefaultSubject::new(1, HashMap::new(), 0), DefaultSubject::new(2, HashMap::new(), 0)];
+    /// let groups = [DefaultGroup::new(101, 2)];
+    /// let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+    /// let mut pairs: Vec<(u32, u32)> = assignment.iter().collect();
+    /// pairs.sort();
+    /// assert_eq!(vec![(1, 101), (2, 101)], pairs);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.subject_ids_to_group_ids
+            .iter()
+            .map(|(subject_id, group_id)| (*subject_id, *group_id))
+    }
+
+    /// An iterator over the groups (given by their ids) together with the ids of their members, in arbitrary order.
+    /// Groups without members may or may not be included depending on how the assignment was obtained.
+    pub fn groups(&self) -> impl Iterator<Item = (u32, &Vec<u32>)> + '_ {
+        self.group_ids_to_subjects_ids
+            .iter()
+            .map(|(group_id, subject_ids)| (*group_id, subject_ids))
+    }
+
+    /// The number of assigned subjects.
+    pub fn number_of_assigned_subjects(&self) -> usize {
+        self.subject_ids_to_group_ids.len()
+    }
+
+    /// The number of groups with at least one member.
+    pub fn number_of_nonempty_groups(&self) -> usize {
+        self.group_ids_to_subjects_ids
+            .values()
+            .filter(|x| !x.is_empty())
+            .count()
+    }
+
+    /// The given subjects that are not assigned to any group, in the order they are given in.
+    pub fn unassigned<'a, S: Subject>(&self, subjects: &'a [S]) -> Vec<&'a S> {
+        subjects
+            .iter()
+            .filter(|x| !self.subject_ids_to_group_ids.contains_key(&x.id()))
+            .collect()
+    }
+
+    /// Lists the [quotas](crate::Quota) of the given groups that are not satisfied by this assignment.
+    /// The categories of the members of a group are looked up among the given subjects.
+    pub fn quota_violations<S: Subject, G: Group>(
+        &self,
+        subjects: &[S],
+        groups: &[G],
+    ) -> Vec<QuotaViolation> {
+        let subjects_by_id: HashMap<u32, &S> = subjects.iter().map(|x| (x.id(), x)).collect();
+        let mut violations = Vec::new();
+        for group in groups {
+            let members: Vec<&S> = self
+                .group_to_subjects_ids(group)
+                .map(|ids| {
+                    ids.iter()
+                        .filter_map(|id| subjects_by_id.get(id))
+                        .copied()
+                        .collect()
+                })
+                .unwrap_or_default();
+            for quota in group.quotas() {
+                let members_in_category = members
+                    .iter()
+                    .filter(|x| x.category(&quota.attribute()) == Some(quota.category()))
+                    .count() as u32;
+                if quota.deviation(members_in_category, members.len() as u32) != (0, 0) {
+                    violations.push(QuotaViolation::new(
+                        group.id(),
+                        quota,
+                        members_in_category,
+                        members.len() as u32,
+                    ));
+                }
+            }
+        }
+        violations
+    }
+
+    // Removes the subject from its group (if any) and returns the id of that group.
+    pub(crate) fn unassign_subject(&mut self, subject_id: u32) -> Option<u32> {
+        let group_id = self.subject_ids_to_group_ids.remove(&subject_id)?;
+        if let Some(members) = self.group_ids_to_subjects_ids.get_mut(&group_id) {
+            members.retain(|x| *x != subject_id);
+        }
+        Some(group_id)
+    }
+
+    // Assigns the subject to the given group, removing it from its previous group (if any).
+    pub(crate) fn assign_subject(&mut self, subject_id: u32, group_id: u32) {
+        self.unassign_subject(subject_id);
+        self.subject_ids_to_group_ids.insert(subject_id, group_id);
+        self.group_ids_to_subjects_ids
+            .entry(group_id)
+            .or_default()
+            .push(subject_id);
+    }
+}
+
+impl PartialEq for Assignment {
+    fn eq(&self, other: &Self) -> bool {
+        let members = |assignment: &Assignment| -> HashMap<u32, Vec<u32>> {
+            assignment
+                .group_ids_to_subjects_ids
+                .iter()
+                .filter(|(_, ids)| !ids.is_empty())
+                .map(|(group_id, ids)| {
+                    let mut ids = ids.clone();
+                    ids.sort_unstable();
+                    (*group_id, ids)
+                })
+                .collect()
+        };
+        self.subject_ids_to_group_ids == other.subject_ids_to_group_ids
+            && members(self) == members(other)
+    }
 }
 
+impl Eq for Assignment {}
+
 impl Default for Assignment {
     fn default() -> Self {
         let subject_ids_to_group_ids: HashMap<u32, u32> = HashMap::new();
@@ -56,3 +211,43 @@ impl From<Assignment>
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn eq_ignores_member_order_and_empty_groups() {
+        let subject_ids_to_group_ids: HashMap<u32, u32> =
+            [(1, 101), (2, 101)].iter().cloned().collect();
+        let first = Assignment::from((
+            subject_ids_to_group_ids.clone(),
+            [(101, vec![1, 2]), (102, vec![])].iter().cloned().collect(),
+        ));
+        let second = Assignment::from((
+            subject_ids_to_group_ids,
+            [(101, vec![2, 1])].iter().cloned().collect(),
+        ));
+        assert_eq!(first, second);
+        assert_eq!(1, first.number_of_nonempty_groups());
+    }
+
+    #[test]
+    fn queries_by_id() {
+        let mut assignment = Assignment::default();
+        assignment.assign_subject(1, 101);
+        assignment.assign_subject(2, 102);
+        assignment.assign_subject(1, 102);
+        assert_eq!(Some(&102), assignment.subject_id_to_group_id(&1));
+        assert_eq!(Some(&vec![2, 1]), assignment.group_id_to_subjects_ids(&102));
+        assert_eq!(2, assignment.number_of_assigned_subjects());
+        let subjects = [TestSubject::new(1, vec![]), TestSubject::new(3, vec![])];
+        let unassigned: Vec<u32> = assignment
+            .unassigned(&subjects)
+            .iter()
+            .map(|x| x.id())
+            .collect();
+        assert_eq!(vec![3], unassigned);
+    }
+}
@@ -0,0 +1,289 @@
+//! # Pairing
+//! This module provides one sided matchings among the subjects themselves, for instance to form peer review or mentoring pairs.
+//!
+//! A subject ranks the other subjects by their [affinity](crate::Subject::affinity) towards them, from highest to lowest.
+//! Subjects with equal affinity are ranked in the order they are given in, and a subject with negative affinity towards another subject
+//! would rather stay unpaired than be paired with them.
+use crate::assignment::errors::{self, AssignmentError};
+use crate::subjects::Subject;
+use std::collections::HashMap;
+
+/// Describes which subjects are paired with each other.
+///
+/// Pairings are typically obtained from [StableRoommates].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pairing {
+    partner_ids: HashMap<u32, u32>,
+    unpaired_ids: Vec<u32>,
+}
+
+impl Pairing {
+    /// Get the id of the subject the given subject is paired with.
+    pub fn partner_id<S: Subject>(&self, subject: &S) -> Option<&u32> {
+        self.partner_ids.get(&subject.id())
+    }
+
+    /// The pairs of subject ids. Every pair is listed once with the smaller id first.
+    pub fn pairs(&self) -> Vec<(u32, u32)> {
+        let mut pairs: Vec<(u32, u32)> = self
+            .partner_ids
+            .iter()
+            .filter(|(x, y)| x < y)
+            .map(|(x, y)| (*x, *y))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// The ids of the subjects that are not paired with anyone.
+    pub fn unpaired_ids(&self) -> &Vec<u32> {
+        &self.unpaired_ids
+    }
+}
+
+/// Pairs subjects according to Irving's stable roommates algorithm.
+///
+/// A pairing is stable if there are no two subjects who would both rather be paired with each other than with their current partners (or than staying unpaired).
+/// Unlike in the case of assigning subjects to groups, stable pairings need not exist. In that case an error is returned.
+///
+/// ```
+/// use group_assignment::DefaultSubject;
+/// use group_assignment::pairing::StableRoommates;
+/// use std::collections::HashMap;
+/// // Every subject prefers the subject with the next id (cyclically) and the subject with the previous id the least
+/// let subjects: Vec<DefaultSubject> = (0..4_u32)
+///     .map(|id| {
+///         let affinities: HashMap<u32, i32> = [((id + 1) % 4, 2), ((id + 2) % 4, 1)].iter().cloned().collect();
+///         DefaultSubject::new(id, HashMap::new(), 0).with_affinities(affinities)
+///     })
+///     .collect();
+/// let pairing = StableRoommates::pair(&subjects).unwrap();
+/// assert_eq!(2, pairing.pairs().len());
+/// assert!(pairing.unpaired_ids().is_empty());
+/// ```
+pub struct StableRoommates {}
+
+impl StableRoommates {
+    /// Pair the given subjects, or report that no stable pairing exists (or that the subject ids are not unique).
+    pub fn pair<S: Subject>(subjects: &[S]) -> Result<Pairing, AssignmentError> {
+        errors::unique_ids(subjects.iter().map(|x| x.id()), std::iter::empty())?;
+        let mut table = PreferenceTable::new(subjects);
+        table.first_phase();
+        table.second_phase()?;
+        let mut partner_ids = HashMap::new();
+        let mut unpaired_ids = Vec::new();
+        for (i, subject) in subjects.iter().enumerate() {
+            match table.first(i) {
+                Some(partner) => {
+                    partner_ids.insert(subject.id(), subjects[partner].id());
+                }
+                None => unpaired_ids.push(subject.id()),
+            }
+        }
+        Ok(Pairing {
+            partner_ids,
+            unpaired_ids,
+        })
+    }
+}
+
+// The (successively reduced) preference lists of Irving's algorithm. Subjects are referred to by their index.
+// The lists are kept symmetric: x appears on the list of y if and only if y appears on the list of x.
+struct PreferenceTable {
+    preferences: Vec<Vec<usize>>,
+    on_list: Vec<Vec<bool>>,
+}
+
+impl PreferenceTable {
+    fn new<S: Subject>(subjects: &[S]) -> Self {
+        let acceptable = |x: &S, y: &S| x.id() != y.id() && x.affinity(&y.id()) >= 0;
+        let preferences: Vec<Vec<usize>> = subjects
+            .iter()
+            .map(|x| {
+                let mut list: Vec<usize> = (0..subjects.len())
+                    .filter(|j| acceptable(x, &subjects[*j]) && acceptable(&subjects[*j], x))
+                    .collect();
+                // A stable sort keeps subjects with equal affinity in the order they are given in
+                list.sort_by_key(|j| -(x.affinity(&subjects[*j].id()) as i64));
+                list
+            })
+            .collect();
+        let mut on_list = vec![vec![false; subjects.len()]; subjects.len()];
+        for (i, list) in preferences.iter().enumerate() {
+            for j in list {
+                on_list[i][*j] = true;
+            }
+        }
+        Self {
+            preferences,
+            on_list,
+        }
+    }
+
+    fn remaining(&self, x: usize) -> impl DoubleEndedIterator<Item = &usize> + '_ {
+        self.preferences[x]
+            .iter()
+            .filter(move |y| self.on_list[x][**y])
+    }
+
+    fn first(&self, x: usize) -> Option<usize> {
+        self.remaining(x).next().copied()
+    }
+
+    fn second(&self, x: usize) -> Option<usize> {
+        self.remaining(x).nth(1).copied()
+    }
+
+    fn last(&self, x: usize) -> Option<usize> {
+        self.remaining(x).next_back().copied()
+    }
+
+    fn remove_pair(&mut self, x: usize, y: usize) {
+        self.on_list[x][y] = false;
+        self.on_list[y][x] = false;
+    }
+
+    // Removes everyone ranked below x from the list of y and returns the removed subjects.
+    fn remove_successors(&mut self, y: usize, x: usize) -> Vec<usize> {
+        let successors: Vec<usize> = self
+            .remaining(y)
+            .skip_while(|z| **z != x)
+            .skip(1)
+            .copied()
+            .collect();
+        for z in successors.iter() {
+            self.remove_pair(y, *z);
+        }
+        successors
+    }
+
+    // Every subject proposes to the first subject on their list. A subject receiving a proposal holds on to it
+    // and rejects everyone they like less, who then propose to the next subject on their lists.
+    fn first_phase(&mut self) {
+        let mut proposers: Vec<usize> = (0..self.preferences.len()).rev().collect();
+        while let Some(x) = proposers.pop() {
+            if let Some(y) = self.first(x) {
+                // Everyone ranked below the held proposal has been removed from the list of y, so y prefers x to the proposal it currently holds
+                let rejected = self.remove_successors(y, x);
+                // Only the previously held proposer has y first on their list and thus needs to propose again
+                proposers.extend(
+                    rejected
+                        .into_iter()
+                        .filter(|z| self.last_proposal_to(*z, y)),
+                );
+            }
+        }
+    }
+
+    // Whether x was holding a proposal to y, i.e. whether y was first on the list of x before y rejected x
+    fn last_proposal_to(&self, x: usize, y: usize) -> bool {
+        self.preferences[x]
+            .iter()
+            .take_while(|z| **z != y)
+            .all(|z| !self.on_list[x][*z])
+    }
+
+    // Eliminates rotations until every list contains at most one subject.
+    fn second_phase(&mut self) -> Result<(), AssignmentError> {
+        let number_of_subjects = self.preferences.len();
+        // Subjects left without partners after the first phase stay unpaired in every stable pairing, whereas everyone else must be paired
+        let must_be_paired: Vec<bool> = (0..number_of_subjects)
+            .map(|x| self.first(x).is_some())
+            .collect();
+        while let Some(start) = (0..number_of_subjects).find(|x| self.second(*x).is_some()) {
+            // Follow p -> last(second(p)) until a subject repeats
+            let mut sequence: Vec<usize> = vec![start];
+            let mut position_in_sequence: HashMap<usize, usize> = [(start, 0)].into();
+            let cycle_start = loop {
+                let p = *sequence.last().unwrap();
+                let q = self.second(p).ok_or_else(no_stable_pairing)?;
+                let next = self.last(q).ok_or_else(no_stable_pairing)?;
+                if let Some(position) = position_in_sequence.get(&next) {
+                    break *position;
+                }
+                position_in_sequence.insert(next, sequence.len());
+                sequence.push(next);
+            };
+            let rotation: Vec<(usize, usize)> = sequence[cycle_start..]
+                .iter()
+                .map(|p| (*p, self.second(*p).unwrap()))
+                .collect();
+            for (i, (_p, q)) in rotation.iter().enumerate() {
+                let (next_p, _) = rotation[(i + 1) % rotation.len()];
+                self.remove_pair(*q, next_p);
+            }
+            for (p, q) in rotation.iter() {
+                self.remove_successors(*q, *p);
+            }
+            if (0..number_of_subjects).any(|x| must_be_paired[x] && self.first(x).is_none()) {
+                return Err(no_stable_pairing());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn no_stable_pairing() -> AssignmentError {
+    AssignmentError::Infeasible {
+        reason: "Every pairing contains two subjects who would rather be paired with each other"
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subjects::DefaultSubject;
+
+    // A subject ranking the given subject ids from most to least preferred
+    fn subject(id: u32, ranking: &[u32]) -> DefaultSubject {
+        let affinities: HashMap<u32, i32> = ranking
+            .iter()
+            .enumerate()
+            .map(|(position, other)| (*other, (ranking.len() - position) as i32))
+            .collect();
+        DefaultSubject::new(id, HashMap::new(), 0).with_affinities(affinities)
+    }
+
+    #[test]
+    fn pair_stable_pairing_exists() {
+        let subjects = [
+            subject(1, &[4, 6, 2, 5, 3]),
+            subject(2, &[6, 3, 5, 1, 4]),
+            subject(3, &[4, 5, 1, 6, 2]),
+            subject(4, &[2, 6, 5, 1, 3]),
+            subject(5, &[4, 2, 3, 6, 1]),
+            subject(6, &[5, 1, 4, 2, 3]),
+        ];
+        let pairing = StableRoommates::pair(&subjects).unwrap();
+        assert_eq!(vec![(1, 6), (2, 3), (4, 5)], pairing.pairs());
+        assert_eq!(Some(&6), pairing.partner_id(&subjects[0]));
+    }
+
+    #[test]
+    fn pair_no_stable_pairing() {
+        // Whoever is paired with the fourth subject would rather be with the subject who ranks them first
+        let subjects = [
+            subject(1, &[2, 3, 4]),
+            subject(2, &[3, 1, 4]),
+            subject(3, &[1, 2, 4]),
+            subject(4, &[1, 2, 3]),
+        ];
+        assert!(StableRoommates::pair(&subjects).is_err());
+    }
+
+    #[test]
+    fn pair_unacceptable_partners() {
+        let mut third_affinities: HashMap<u32, i32> = HashMap::new();
+        third_affinities.insert(1, -1);
+        third_affinities.insert(2, -1);
+        let subjects = [
+            subject(1, &[3, 2]),
+            subject(2, &[1, 3]),
+            DefaultSubject::new(3, HashMap::new(), 0).with_affinities(third_affinities),
+        ];
+        let pairing = StableRoommates::pair(&subjects).unwrap();
+        assert_eq!(vec![(1, 2)], pairing.pairs());
+        assert_eq!(&vec![3], pairing.unpaired_ids());
+    }
+}
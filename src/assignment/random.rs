@@ -0,0 +1,36 @@
+//! # Random numbers
+//! Crate private helper module providing reproducible pseudo random numbers.
+//!
+//! Results of this library must only depend on their inputs (including seeds), hence we use the small seeded SplitMix64 generator
+//! rather than randomness provided by the operating system.
+
+/// Scrambles the bits of the given number. Equal inputs give equal outputs, while similar inputs give unrelated outputs.
+pub(crate) fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A seeded SplitMix64 generator producing a reproducible stream of pseudo random numbers.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let x = self.state;
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(x)
+    }
+
+    /// A number in [0, 1), using the 53 bits of precision of a double.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
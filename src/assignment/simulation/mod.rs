@@ -0,0 +1,391 @@
+//! # Simulation
+//! This module estimates the outcomes of randomized assigners by running them many times with different seeds.
+//!
+//! This answers questions such as "how likely is this subject to get into its favourite group?" before the actual assignment takes place.
+use super::assigners::Assigner;
+use super::errors::AssignmentError;
+use super::tie_breaking::TieBreaking;
+use super::Assignment;
+use crate::groups::{effective_capacity, Group};
+use crate::subjects::Subject;
+use std::collections::HashMap;
+
+/// Statistics about the number of members of a group over all runs of a [simulation](Simulation).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStatistics {
+    group_id: u32,
+    mean_members: f64,
+    min_members: usize,
+    max_members: usize,
+    full_runs: usize,
+}
+
+impl GroupStatistics {
+    /// The id of the group.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// The average number of members.
+    pub fn mean_members(&self) -> f64 {
+        self.mean_members
+    }
+
+    /// The smallest number of members in any run.
+    pub fn min_members(&self) -> usize {
+        self.min_members
+    }
+
+    /// The largest number of members in any run.
+    pub fn max_members(&self) -> usize {
+        self.max_members
+    }
+
+    /// The number of runs in which the group was filled to capacity.
+    pub fn full_runs(&self) -> usize {
+        self.full_runs
+    }
+}
+
+/// The aggregated outcomes of a [simulation](Simulation).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    runs: usize,
+    subject_ids: Vec<u32>,
+    group_ids: Vec<u32>,
+    probabilities: Vec<Vec<f64>>,
+    group_statistics: Vec<GroupStatistics>,
+}
+
+impl SimulationReport {
+    /// The number of runs the report is based on.
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// The ids of the subjects, in the order of the rows of the [probabilities](SimulationReport::probabilities).
+    pub fn subject_ids(&self) -> &Vec<u32> {
+        &self.subject_ids
+    }
+
+    /// The ids of the groups, in the order of the columns of the [probabilities](SimulationReport::probabilities).
+    pub fn group_ids(&self) -> &Vec<u32> {
+        &self.group_ids
+    }
+
+    /// The matrix of estimated probabilities with one row per subject and one column per group,
+    /// that is the fraction of runs in which the subject was assigned to the group.
+    pub fn probabilities(&self) -> &Vec<Vec<f64>> {
+        &self.probabilities
+    }
+
+    /// The estimated probability of the subject with the given id ending up in the group with the given id, which is zero for unknown ids.
+    pub fn probability(&self, subject_id: u32, group_id: u32) -> f64 {
+        match (
+            self.subject_ids.iter().position(|x| *x == subject_id),
+            self.group_ids.iter().position(|x| *x == group_id),
+        ) {
+            (Some(i), Some(j)) => self.probabilities[i][j],
+            _ => 0.0,
+        }
+    }
+
+    /// The statistics of every group, in the given order of the groups.
+    pub fn group_statistics(&self) -> &Vec<GroupStatistics> {
+        &self.group_statistics
+    }
+}
+
+// What a number of runs amount to, before averaging.
+struct Tally {
+    runs: usize,
+    counts: Vec<Vec<usize>>,
+    member_sums: Vec<usize>,
+    min_members: Vec<usize>,
+    max_members: Vec<usize>,
+    full_runs: Vec<usize>,
+}
+
+impl Tally {
+    fn new(number_of_subjects: usize, number_of_groups: usize) -> Self {
+        Self {
+            runs: 0,
+            counts: vec![vec![0; number_of_groups]; number_of_subjects],
+            member_sums: vec![0; number_of_groups],
+            min_members: vec![usize::MAX; number_of_groups],
+            max_members: vec![0; number_of_groups],
+            full_runs: vec![0; number_of_groups],
+        }
+    }
+
+    fn add(
+        &mut self,
+        assignment: &Assignment,
+        subject_positions: &HashMap<u32, usize>,
+        group_positions: &HashMap<u32, usize>,
+        capacities: &[usize],
+    ) {
+        let mut members = vec![0; capacities.len()];
+        for (subject_id, group_id) in assignment.iter() {
+            if let (Some(i), Some(j)) = (
+                subject_positions.get(&subject_id),
+                group_positions.get(&group_id),
+            ) {
+                self.counts[*i][*j] += 1;
+                members[*j] += 1;
+            }
+        }
+        for (j, members) in members.into_iter().enumerate() {
+            self.member_sums[j] += members;
+            self.min_members[j] = self.min_members[j].min(members);
+            self.max_members[j] = self.max_members[j].max(members);
+            if members >= capacities[j] {
+                self.full_runs[j] += 1;
+            }
+        }
+        self.runs += 1;
+    }
+
+    fn merge(&mut self, other: Tally) {
+        for (row, other_row) in self.counts.iter_mut().zip(other.counts) {
+            for (count, other_count) in row.iter_mut().zip(other_row) {
+                *count += other_count;
+            }
+        }
+        for j in 0..self.member_sums.len() {
+            self.member_sums[j] += other.member_sums[j];
+            self.min_members[j] = self.min_members[j].min(other.min_members[j]);
+            self.max_members[j] = self.max_members[j].max(other.max_members[j]);
+            self.full_runs[j] += other.full_runs[j];
+        }
+        self.runs += other.runs;
+    }
+}
+
+/// Runs a randomized assigner many times, with consecutive seeds, and aggregates the outcomes into a [report](SimulationReport).
+///
+/// Runs can be spread over several threads. The report only depends on the seeds, not on the number of threads.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::assigners::FirstComeFirstServed;
+/// use group_assignment::simulation::Simulation;
+/// use std::collections::HashMap;
+/// let subjects = [DefaultSubject::new(1, HashMap::new(), 0), DefaultSubject::new(2, HashMap::new(), 0)];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// // Subjects that do not care about their group are placed by the seeded tie-breaking policy
+/// let report = Simulation::new(1000)
+///     .with_threads(4)
+///     .run_assigner::<FirstComeFirstServed, _, _>(&subjects, &groups)
+///     .unwrap();
+/// assert!((report.probability(1, 101) - 0.5).abs() < 0.1);
+/// assert_eq!(1, report.group_statistics()[0].max_members());
+/// ```
+///
+/// Any source of random assignments can be simulated, for instance a [lottery](crate::lotteries::Lottery):
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject};
+/// use group_assignment::lotteries::ProbabilisticSerial;
+/// use group_assignment::simulation::Simulation;
+/// let prefer_first = |id| DefaultSubject::new(id, [(101, 0), (102, 1)].iter().cloned().collect(), 2);
+/// let subjects = [prefer_first(1), prefer_first(2)];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// let lottery = ProbabilisticSerial::random_assignment(&subjects, &groups).unwrap().lottery().unwrap();
+/// let report = Simulation::new(1000)
+///     .run(&subjects, &groups, |seed| Ok(lottery.sample(seed).clone()))
+///     .unwrap();
+/// assert!((report.probability(2, 101) - 0.5).abs() < 0.1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    runs: usize,
+    first_seed: u64,
+    threads: usize,
+}
+
+impl Simulation {
+    /// A simulation with the given number of runs, using the seeds 0, 1, 2, ... on a single thread.
+    pub fn new(runs: usize) -> Self {
+        Self {
+            runs,
+            first_seed: 0,
+            threads: 1,
+        }
+    }
+
+    /// Sets the seed of the first run. The following runs use the following seeds, wrapping around.
+    pub fn with_first_seed(mut self, first_seed: u64) -> Self {
+        self.first_seed = first_seed;
+        self
+    }
+
+    /// Sets the number of threads the runs are spread over. Zero counts as one.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Simulates an [assigner](Assigner) breaking ties with the [seeded policy](TieBreaking::Seeded).
+    pub fn run_assigner<A: Assigner, S: Subject + Sync, G: Group + Sync>(
+        &self,
+        subjects: &[S],
+        groups: &[G],
+    ) -> Result<SimulationReport, AssignmentError> {
+        self.run(subjects, groups, |seed| {
+            A::assign_with_tie_breaking(subjects, groups, TieBreaking::Seeded(seed))
+        })
+    }
+
+    /// Simulates the given function, which assigns the given subjects to the given groups for a seed.
+    ///
+    /// If a run fails, the error of the run with the lowest seed (counting from the first seed) is returned.
+    pub fn run<S: Subject, G: Group, F>(
+        &self,
+        subjects: &[S],
+        groups: &[G],
+        assign: F,
+    ) -> Result<SimulationReport, AssignmentError>
+    where
+        F: Fn(u64) -> Result<Assignment, AssignmentError> + Sync,
+    {
+        let subject_ids: Vec<u32> = subjects.iter().map(|s| s.id()).collect();
+        let group_ids: Vec<u32> = groups.iter().map(|g| g.id()).collect();
+        let capacities: Vec<usize> = groups
+            .iter()
+            .map(|g| effective_capacity(g, subjects.len()))
+            .collect();
+        let subject_positions: HashMap<u32, usize> = subject_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let group_positions: HashMap<u32, usize> = group_ids
+            .iter()
+            .enumerate()
+            .map(|(j, id)| (*id, j))
+            .collect();
+        let tally_runs = |runs: std::ops::Range<usize>| -> Result<Tally, AssignmentError> {
+            let mut tally = Tally::new(subject_ids.len(), group_ids.len());
+            for run in runs {
+                let assignment = assign(self.first_seed.wrapping_add(run as u64))?;
+                tally.add(
+                    &assignment,
+                    &subject_positions,
+                    &group_positions,
+                    &capacities,
+                );
+            }
+            Ok(tally)
+        };
+        let chunk_size = self.runs.div_ceil(self.threads).max(1);
+        let tallies: Vec<Result<Tally, AssignmentError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.runs)
+                .step_by(chunk_size)
+                .map(|start| {
+                    let tally_runs = &tally_runs;
+                    scope.spawn(move || tally_runs(start..(start + chunk_size).min(self.runs)))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("a simulation run panicked"))
+                .collect()
+        });
+        let mut total = Tally::new(subject_ids.len(), group_ids.len());
+        for tally in tallies {
+            total.merge(tally?);
+        }
+
+        let runs = total.runs.max(1) as f64;
+        let probabilities = total
+            .counts
+            .iter()
+            .map(|row| row.iter().map(|count| *count as f64 / runs).collect())
+            .collect();
+        let group_statistics = group_ids
+            .iter()
+            .enumerate()
+            .map(|(j, group_id)| GroupStatistics {
+                group_id: *group_id,
+                mean_members: total.member_sums[j] as f64 / runs,
+                min_members: if total.runs == 0 {
+                    0
+                } else {
+                    total.min_members[j]
+                },
+                max_members: total.max_members[j],
+                full_runs: total.full_runs[j],
+            })
+            .collect();
+        Ok(SimulationReport {
+            runs: total.runs,
+            subject_ids,
+            group_ids,
+            probabilities,
+            group_statistics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::ProposeAndReject;
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    #[test]
+    fn simulation_does_not_depend_on_threads() {
+        let group_ids = [101_u32, 102, 103];
+        let subjects: Vec<TestSubject> = (0..9_u32)
+            .map(|id| TestSubject::new(id, vec![group_ids[(id % 2) as usize]]))
+            .collect();
+        let groups = [
+            DefaultGroup::new(group_ids[0], 2),
+            DefaultGroup::new(group_ids[1], 4),
+            DefaultGroup::new(group_ids[2], 4),
+        ];
+        let simulation = Simulation::new(50).with_first_seed(u64::MAX - 10);
+        let report = simulation
+            .run_assigner::<ProposeAndReject, _, _>(&subjects, &groups)
+            .unwrap();
+        let parallel_report = simulation
+            .with_threads(3)
+            .run_assigner::<ProposeAndReject, _, _>(&subjects, &groups)
+            .unwrap();
+        assert_eq!(report, parallel_report);
+        assert_eq!(50, report.runs());
+        for row in report.probabilities() {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+        // The five subjects wanting the first group compete for two seats, the four subjects wanting the second group fit into it
+        assert_eq!(50, report.group_statistics()[0].full_runs());
+        assert_eq!(
+            (2, 2),
+            (
+                report.group_statistics()[0].min_members(),
+                report.group_statistics()[0].max_members()
+            )
+        );
+        assert_eq!(1.0, report.probability(1, group_ids[1]));
+        let first_group: f64 = (0..9)
+            .step_by(2)
+            .map(|id| report.probability(id, group_ids[0]))
+            .sum();
+        assert!((first_group - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulation_reports_errors() {
+        let subjects = [TestSubject::new(1, vec![]), TestSubject::new(2, vec![])];
+        let groups = [DefaultGroup::new(101, 1)];
+        let result = Simulation::new(10)
+            .with_threads(2)
+            .run_assigner::<ProposeAndReject, _, _>(&subjects, &groups);
+        assert!(matches!(
+            result,
+            Err(AssignmentError::InsufficientCapacity { .. })
+        ));
+    }
+}
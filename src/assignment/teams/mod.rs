@@ -0,0 +1,279 @@
+//! # Teams
+//! This module provides team formation: splitting subjects into groups that are created on the fly rather than given in advance.
+//!
+//! The subjects are partitioned so that the total [affinity](crate::Subject::affinity) between members of the same team is high.
+use super::group_management::{assign_from_group_registries, SimpleGroupRegistry};
+use super::Assignment;
+use crate::assignment::errors::{self, AssignmentError};
+use crate::groups::DefaultGroup;
+use crate::subjects::Subject;
+
+/// Splits subjects into teams whose sizes lie within given bounds.
+///
+/// The number of teams is chosen as small as the bounds allow and the team sizes differ by at most one.
+/// Starting from the subjects in the given order, members are moved between teams or swapped with each other as long as this
+/// increases the total mutual affinity, that is the sum of `a.affinity(&b.id())` over all ordered pairs of distinct subjects `a` and `b` in the same team.
+///
+/// ```
+/// use group_assignment::DefaultSubject;
+/// use group_assignment::teams::TeamFormation;
+/// use std::collections::HashMap;
+/// let friends = |id: u32, friend: u32| {
+///     let affinities: HashMap<u32, i32> = [(friend, 1)].iter().cloned().collect();
+///     DefaultSubject::new(id, HashMap::new(), 0).with_affinities(affinities)
+/// };
+/// let subjects = [friends(1, 3), friends(2, 4), friends(3, 1), friends(4, 2)];
+/// let (assignment, teams) = TeamFormation::new(2, 2).form_teams(&subjects).unwrap();
+/// assert_eq!(2, teams.len());
+/// assert_eq!(
+///     assignment.subject_to_group_id(&subjects[0]),
+///     assignment.subject_to_group_id(&subjects[2])
+/// );
+/// ```
+pub struct TeamFormation {
+    min_size: u32,
+    max_size: u32,
+}
+
+impl TeamFormation {
+    /// Constructor. Both bounds on the team sizes are inclusive.
+    pub fn new(min_size: u32, max_size: u32) -> Self {
+        Self { min_size, max_size }
+    }
+
+    /// Splits the given subjects into teams.
+    ///
+    /// The created teams are returned alongside the assignment. They have ids 0, 1, 2, ... and their capacity is the maximal team size.
+    /// An error is returned if the subject ids are not unique, if the bounds are invalid or if the subjects can not be split into teams of the requested sizes.
+    pub fn form_teams<S: Subject>(
+        &self,
+        subjects: &[S],
+    ) -> Result<(Assignment, Vec<DefaultGroup>), AssignmentError> {
+        errors::unique_ids(subjects.iter().map(|x| x.id()), std::iter::empty())?;
+        let sizes = self.team_sizes(subjects.len())?;
+        let mut search = TeamSearch::new(subjects, &sizes);
+        // Moves keep every team size between the smallest and the largest of the initial sizes, which differ by at most one
+        search.improve(
+            sizes.iter().min().copied().unwrap_or(0),
+            sizes.iter().max().copied().unwrap_or(0),
+        );
+        let teams: Vec<DefaultGroup> = (0..sizes.len())
+            .map(|t| DefaultGroup::new(t as u32, self.max_size))
+            .collect();
+        let mut group_registries: Vec<_> = teams
+            .iter()
+            .map(|g| SimpleGroupRegistry::new(g, Vec::new()))
+            .collect();
+        for (subject, team) in subjects.iter().zip(search.placement.iter()) {
+            group_registries[*team].subjects.push(subject);
+        }
+        Ok((assign_from_group_registries(group_registries), teams))
+    }
+
+    // The sizes of the teams: as few teams as possible with sizes as equal as possible.
+    fn team_sizes(&self, number_of_subjects: usize) -> Result<Vec<usize>, AssignmentError> {
+        let (min_size, max_size) = (self.min_size as usize, self.max_size as usize);
+        if max_size == 0 || min_size > max_size {
+            return Err(AssignmentError::InvalidInput {
+                reason: format!(
+                    "There are no team sizes between {} and {}",
+                    min_size, max_size
+                ),
+            });
+        }
+        let number_of_teams = number_of_subjects.div_ceil(max_size);
+        if number_of_teams * min_size > number_of_subjects {
+            return Err(AssignmentError::Infeasible {
+                reason: format!(
+                    "{} subjects can not be split into teams of sizes between {} and {}",
+                    number_of_subjects, min_size, max_size
+                ),
+            });
+        }
+        Ok((0..number_of_teams)
+            .map(|t| {
+                number_of_subjects / number_of_teams
+                    + usize::from(t < number_of_subjects % number_of_teams)
+            })
+            .collect())
+    }
+}
+
+// Local search state. Subjects and teams are referred to by their index.
+struct TeamSearch {
+    // mutual_affinity[i][k] is the affinity of the i'th subject towards the k'th subject plus the reverse affinity
+    mutual_affinity: Vec<Vec<i64>>,
+    placement: Vec<usize>,
+    sizes: Vec<usize>,
+    // affinity_to_team[i][t] is the sum of the mutual affinities between the i'th subject and the members of team t
+    affinity_to_team: Vec<Vec<i64>>,
+}
+
+impl TeamSearch {
+    fn new<S: Subject>(subjects: &[S], sizes: &[usize]) -> Self {
+        let mutual_affinity: Vec<Vec<i64>> = subjects
+            .iter()
+            .map(|a| {
+                subjects
+                    .iter()
+                    .map(|b| {
+                        if a.id() == b.id() {
+                            0
+                        } else {
+                            a.affinity(&b.id()) as i64 + b.affinity(&a.id()) as i64
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let placement: Vec<usize> = sizes
+            .iter()
+            .enumerate()
+            .flat_map(|(t, size)| std::iter::repeat_n(t, *size))
+            .collect();
+        let affinity_to_team = mutual_affinity
+            .iter()
+            .map(|row| {
+                let mut sums = vec![0; sizes.len()];
+                for (k, affinity) in row.iter().enumerate() {
+                    sums[placement[k]] += affinity;
+                }
+                sums
+            })
+            .collect();
+        Self {
+            mutual_affinity,
+            placement,
+            sizes: sizes.to_vec(),
+            affinity_to_team,
+        }
+    }
+
+    fn relocate(&mut self, subject: usize, to: usize) {
+        let from = self.placement[subject];
+        for (sums, row) in self
+            .affinity_to_team
+            .iter_mut()
+            .zip(self.mutual_affinity.iter())
+        {
+            sums[from] -= row[subject];
+            sums[to] += row[subject];
+        }
+        self.sizes[from] -= 1;
+        self.sizes[to] += 1;
+        self.placement[subject] = to;
+    }
+
+    // Applies improving moves and swaps until none are left.
+    fn improve(&mut self, min_size: usize, max_size: usize) {
+        let number_of_subjects = self.placement.len();
+        let number_of_teams = self.sizes.len();
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..number_of_subjects {
+                for to in 0..number_of_teams {
+                    let from = self.placement[i];
+                    if to != from
+                        && self.sizes[from] > min_size
+                        && self.sizes[to] < max_size
+                        && self.affinity_to_team[i][to] > self.affinity_to_team[i][from]
+                    {
+                        self.relocate(i, to);
+                        improved = true;
+                    }
+                }
+            }
+            for i in 0..number_of_subjects {
+                for k in (i + 1)..number_of_subjects {
+                    let (a, b) = (self.placement[i], self.placement[k]);
+                    if a == b {
+                        continue;
+                    }
+                    let gain = self.affinity_to_team[i][b]
+                        - self.mutual_affinity[i][k]
+                        - self.affinity_to_team[i][a]
+                        + self.affinity_to_team[k][a]
+                        - self.mutual_affinity[k][i]
+                        - self.affinity_to_team[k][b];
+                    if gain > 0 {
+                        self.relocate(i, b);
+                        self.relocate(k, a);
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subjects::DefaultSubject;
+    use std::collections::HashMap;
+
+    fn subject(id: u32, affinities: &[(u32, i32)]) -> DefaultSubject {
+        DefaultSubject::new(id, HashMap::new(), 0)
+            .with_affinities(affinities.iter().cloned().collect())
+    }
+
+    #[test]
+    fn team_sizes() {
+        assert_eq!(vec![4, 3], TeamFormation::new(3, 4).team_sizes(7).unwrap());
+        assert_eq!(
+            vec![3, 3, 3],
+            TeamFormation::new(2, 3).team_sizes(9).unwrap()
+        );
+        assert!(TeamFormation::new(4, 5).team_sizes(7).is_err());
+        assert!(TeamFormation::new(3, 2).team_sizes(6).is_err());
+        assert!(TeamFormation::new(0, 0).team_sizes(6).is_err());
+    }
+
+    #[test]
+    fn form_teams_keeps_sizes_balanced() {
+        // The fourth subject likes the first one, which would be worth a move if the bounds were all that mattered
+        let subjects = [
+            subject(1, &[]),
+            subject(2, &[]),
+            subject(3, &[]),
+            subject(4, &[(1, 5)]),
+            subject(5, &[]),
+            subject(6, &[]),
+        ];
+        let (assignment, teams) = TeamFormation::new(1, 4).form_teams(&subjects).unwrap();
+        let team_sizes: Vec<usize> = teams
+            .iter()
+            .map(|t| assignment.group_to_subjects_ids(t).unwrap().len())
+            .collect();
+        assert_eq!(vec![3, 3], team_sizes);
+        // The fourth subject joins the first one by a swap instead
+        assert_eq!(
+            assignment.subject_to_group_id(&subjects[0]),
+            assignment.subject_to_group_id(&subjects[3])
+        );
+    }
+
+    #[test]
+    fn form_teams_separates_rivals() {
+        // The first two subjects can not stand each other, and the third subject likes the first one.
+        let subjects = [
+            subject(1, &[(2, -10)]),
+            subject(2, &[(1, -10)]),
+            subject(3, &[(1, 3)]),
+            subject(4, &[]),
+            subject(5, &[]),
+        ];
+        let (assignment, teams) = TeamFormation::new(2, 3).form_teams(&subjects).unwrap();
+        assert_eq!(2, teams.len());
+        let team_of = |i: usize| assignment.subject_to_group_id(&subjects[i]).copied();
+        assert_ne!(team_of(0), team_of(1));
+        assert_eq!(team_of(0), team_of(2));
+        let team_sizes: Vec<usize> = teams
+            .iter()
+            .map(|t| assignment.group_to_subjects_ids(t).unwrap().len())
+            .collect();
+        assert!(team_sizes.iter().all(|size| (2..=3).contains(size)));
+        assert_eq!(5, team_sizes.iter().sum::<usize>());
+    }
+}
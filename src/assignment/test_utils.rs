@@ -0,0 +1,39 @@
+/// Module providing helpers for building assignments in tests.
+use crate::assignment::Assignment;
+use crate::groups::DefaultGroup;
+use crate::subjects::test_utils::TestSubject;
+use std::collections::HashMap;
+
+/// The assignment given by pairs of subject ids and group ids.
+pub(crate) fn assignment(subject_ids_to_group_ids: &[(u32, u32)]) -> Assignment {
+    let mut group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (subject_id, group_id) in subject_ids_to_group_ids {
+        group_ids_to_subjects_ids
+            .entry(*group_id)
+            .or_default()
+            .push(*subject_id);
+    }
+    Assignment::from((
+        subject_ids_to_group_ids.iter().cloned().collect(),
+        group_ids_to_subjects_ids,
+    ))
+}
+
+/// Twelve subjects with two choices each, rotating through three groups of different capacities,
+/// such that the smallest group is wanted by more subjects than it can take.
+pub(crate) fn contested_instance() -> (Vec<TestSubject>, Vec<DefaultGroup>) {
+    let group_ids = [101_u32, 102, 103];
+    let subjects = (0..12_u32)
+        .map(|id| {
+            let first = (id % 3) as usize;
+            let second = ((id / 3) % 3) as usize;
+            TestSubject::new(id, vec![group_ids[first], group_ids[second]])
+        })
+        .collect();
+    let groups = vec![
+        DefaultGroup::new(group_ids[0], 2),
+        DefaultGroup::new(group_ids[1], 4),
+        DefaultGroup::new(group_ids[2], 6),
+    ];
+    (subjects, groups)
+}
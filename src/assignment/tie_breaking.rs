@@ -0,0 +1,112 @@
+//! # Tie-breaking
+//! This module decides between groups a subject is equally dissatisfied with.
+use super::random;
+use crate::groups::Group;
+use std::collections::HashMap;
+
+/// Policies deciding which group a subject is placed in when several groups are equally good choices.
+///
+/// Every policy is deterministic: running an assigner twice on the same subjects and groups (given in the same order)
+/// with the same policy yields the same assignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum TieBreaking {
+    /// Prefer the group that comes first in the given groups.
+    #[default]
+    GroupOrder,
+    /// Prefer the group with the smallest id.
+    GroupId,
+    /// Prefer groups in a pseudo random order determined by the seed. Every subject has its own order.
+    Seeded(u64),
+    /// Prefer the group with the fewest members, and among those the group that comes first in the given groups.
+    LeastFilled,
+    /// Prefer the group with the lowest ratio of members to capacity, and among those the group that comes first in the given groups.
+    /// Groups with [unlimited capacity](crate::Group::has_unlimited_capacity) count as empty.
+    ///
+    /// This spreads subjects evenly over interchangeable groups of different sizes. See also [Assignment::balance](crate::Assignment::balance).
+    LowestFillRatio,
+}
+
+/// Applies a tie-breaking policy to the groups an assigner was given.
+#[derive(Debug, Clone)]
+pub(crate) struct TieBreaker {
+    policy: TieBreaking,
+    group_positions: HashMap<u32, usize>,
+    capacities: Vec<u32>,
+}
+
+impl TieBreaker {
+    pub(crate) fn new<G: Group>(policy: TieBreaking, groups: &[G]) -> Self {
+        Self {
+            policy,
+            group_positions: groups
+                .iter()
+                .enumerate()
+                .map(|(position, g)| (g.id(), position))
+                .collect(),
+            capacities: groups
+                .iter()
+                .map(|g| {
+                    if g.has_unlimited_capacity() {
+                        0
+                    } else {
+                        g.capacity()
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Among groups the subject is equally dissatisfied with, the group with the smallest key is preferred.
+    pub(crate) fn key(&self, subject_id: u32, group_id: u32, members: usize) -> (u64, usize) {
+        let position = self
+            .group_positions
+            .get(&group_id)
+            .copied()
+            .unwrap_or(usize::MAX);
+        match self.policy {
+            TieBreaking::GroupOrder => (0, position),
+            TieBreaking::GroupId => (group_id as u64, position),
+            TieBreaking::Seeded(seed) => (
+                random::mix(seed ^ random::mix(((subject_id as u64) << 32) | group_id as u64)),
+                position,
+            ),
+            TieBreaking::LeastFilled => (members as u64, position),
+            TieBreaking::LowestFillRatio => (self.fill_ratio(position, members), position),
+        }
+    }
+
+    // The ratio of members to capacity as a fixed point number with 32 fractional bits, where unlimited groups count as empty.
+    fn fill_ratio(&self, position: usize, members: usize) -> u64 {
+        match self.capacities.get(position) {
+            Some(0) => 0,
+            Some(capacity) => ((members as u64) << 32) / *capacity as u64,
+            None => u64::MAX,
+        }
+    }
+
+    /// For every group, its rank (0 for the most preferred) among the given groups according to the policy when all groups are empty.
+    /// Assigners placing all subjects at once use these ranks as a secondary objective.
+    pub(crate) fn ranks<G: Group>(&self, subject_id: u32, groups: &[G]) -> Vec<i64> {
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.sort_by_key(|j| self.key(subject_id, groups[*j].id(), 0));
+        let mut ranks = vec![0; groups.len()];
+        for (rank, j) in order.into_iter().enumerate() {
+            ranks[j] = rank as i64;
+        }
+        ranks
+    }
+
+    /// For policies taking the number of members into account: the tie cost of the k'th seat (counting from 0) of the j'th group.
+    /// Assigners placing all subjects at once use these costs to spread the subjects.
+    pub(crate) fn seat_costs(&self) -> Option<Box<dyn Fn(usize, usize) -> i64 + '_>> {
+        match self.policy {
+            TieBreaking::LeastFilled => Some(Box::new(|_j, k| k as i64)),
+            // Dropping some precision keeps the scaled costs of the assigners well within range
+            TieBreaking::LowestFillRatio => {
+                Some(Box::new(move |j, k| (self.fill_ratio(j, k) >> 16) as i64))
+            }
+            _ => None,
+        }
+    }
+}
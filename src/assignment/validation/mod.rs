@@ -0,0 +1,268 @@
+//! # Validation
+//! This module checks assignments against the subjects and groups they are supposed to describe.
+//!
+//! Assignments provided by the [assigners](crate::assigners) are always valid, but assignments that are built from a pair of maps
+//! (for instance after being loaded from a file or edited by hand) need not be.
+use super::Assignment;
+use crate::groups::Group;
+use crate::subjects::Subject;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Describes a way in which an assignment is inconsistent, either in itself or with the given subjects and groups.
+///
+/// Violations are ordered by kind, in the order the kinds are declared in, and then by their fields.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Violation {
+    /// The subject is mapped to the group, but the group does not list the subject as a member.
+    NotListedAsMember { subject_id: u32, group_id: u32 },
+    /// The group lists the subject as a member, but the subject is not mapped to the group.
+    NotMappedToGroup { subject_id: u32, group_id: u32 },
+    /// The subject is listed as a member more than once, either by several groups or repeatedly by the same group.
+    MultipleMemberships {
+        subject_id: u32,
+        group_ids: Vec<u32>,
+    },
+    /// The group has more members than its capacity allows.
+    OverCapacity {
+        group_id: u32,
+        members: u32,
+        capacity: u32,
+    },
+    /// The assignment refers to a subject id that does not belong to any of the given subjects.
+    UnknownSubject { subject_id: u32 },
+    /// The assignment refers to a group id that does not belong to any of the given groups.
+    UnknownGroup { group_id: u32 },
+}
+
+impl Assignment {
+    /// Checks that the two maps describing the assignment are inverse to each other, that every subject is assigned at most once,
+    /// that no group has more members than its capacity and that all ids belong to the given subjects and groups.
+    ///
+    /// Returns every violation that was found, ordered by kind and then by ascending ids. An empty list means that the assignment is valid.
+    ///
+    /// ```
+    /// use group_assignment::{Assignment, DefaultGroup, DefaultSubject};
+    /// use group_assignment::validation::Violation;
+    /// use std::collections::HashMap;
+    /// let subjects = [DefaultSubject::new(1, HashMap::new(), 0), DefaultSubject::new(2, HashMap::new(), 0)];
+    /// let groups = [DefaultGroup::new(101, 1)];
+    /// let subject_ids_to_group_ids: HashMap<u32, u32> = [(1, 101), (2, 101)].iter().cloned().collect();
+    /// let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> = [(101, vec![1, 2])].iter().cloned().collect();
+    /// let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+    /// assert_eq!(
+    ///     vec![Violation::OverCapacity { group_id: 101, members: 2, capacity: 1 }],
+    ///     assignment.validate(&subjects, &groups)
+    /// );
+    /// ```
+    pub fn validate<S: Subject, G: Group>(&self, subjects: &[S], groups: &[G]) -> Vec<Violation> {
+        let subject_ids: BTreeSet<u32> = subjects.iter().map(|s| s.id()).collect();
+        // Groups with unlimited capacity can not be over capacity
+        let capacities: BTreeMap<u32, Option<u32>> = groups
+            .iter()
+            .map(|g| (g.id(), (!g.has_unlimited_capacity()).then(|| g.capacity())))
+            .collect();
+        let subject_ids_to_group_ids: BTreeMap<u32, u32> = self
+            .subject_ids_to_group_ids
+            .iter()
+            .map(|(s, g)| (*s, *g))
+            .collect();
+        let group_ids_to_subjects_ids: BTreeMap<u32, &Vec<u32>> = self
+            .group_ids_to_subjects_ids
+            .iter()
+            .map(|(g, s)| (*g, s))
+            .collect();
+
+        let mut violations = Vec::new();
+        let mut unknown_subjects = BTreeSet::new();
+        let mut unknown_groups = BTreeSet::new();
+        for (subject_id, group_id) in subject_ids_to_group_ids.iter() {
+            if !group_ids_to_subjects_ids
+                .get(group_id)
+                .is_some_and(|members| members.contains(subject_id))
+            {
+                violations.push(Violation::NotListedAsMember {
+                    subject_id: *subject_id,
+                    group_id: *group_id,
+                });
+            }
+            if !subject_ids.contains(subject_id) {
+                unknown_subjects.insert(*subject_id);
+            }
+            if !capacities.contains_key(group_id) {
+                unknown_groups.insert(*group_id);
+            }
+        }
+        let mut memberships: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (group_id, members) in group_ids_to_subjects_ids.iter() {
+            for subject_id in members.iter() {
+                memberships.entry(*subject_id).or_default().push(*group_id);
+                if subject_ids_to_group_ids.get(subject_id) != Some(group_id) {
+                    violations.push(Violation::NotMappedToGroup {
+                        subject_id: *subject_id,
+                        group_id: *group_id,
+                    });
+                }
+                if !subject_ids.contains(subject_id) {
+                    unknown_subjects.insert(*subject_id);
+                }
+            }
+            match capacities.get(group_id) {
+                Some(Some(capacity)) if members.len() as u64 > *capacity as u64 => {
+                    violations.push(Violation::OverCapacity {
+                        group_id: *group_id,
+                        members: members.len() as u32,
+                        capacity: *capacity,
+                    });
+                }
+                None if !members.is_empty() => {
+                    unknown_groups.insert(*group_id);
+                }
+                _ => {}
+            }
+        }
+        violations.extend(
+            memberships
+                .into_iter()
+                .filter(|(_, group_ids)| group_ids.len() > 1)
+                .map(|(subject_id, group_ids)| Violation::MultipleMemberships {
+                    subject_id,
+                    group_ids,
+                }),
+        );
+        violations.extend(
+            unknown_subjects
+                .into_iter()
+                .map(|subject_id| Violation::UnknownSubject { subject_id }),
+        );
+        violations.extend(
+            unknown_groups
+                .into_iter()
+                .map(|group_id| Violation::UnknownGroup { group_id }),
+        );
+        violations.sort();
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::{Assigner, ProposeAndReject};
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+    use std::collections::HashMap;
+
+    #[test]
+    fn validate_assigner_output() {
+        let subjects = [
+            TestSubject::new(1, vec![101, 102]),
+            TestSubject::new(2, vec![101, 102]),
+            TestSubject::new(3, vec![102]),
+        ];
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 2)];
+        let assignment = ProposeAndReject::assign(&subjects, &groups).unwrap();
+        assert!(assignment.validate(&subjects, &groups).is_empty());
+    }
+
+    #[test]
+    fn validate_inconsistent_maps() {
+        let subjects = [TestSubject::new(1, vec![]), TestSubject::new(2, vec![])];
+        let groups = [DefaultGroup::new(101, 5), DefaultGroup::new(102, 5)];
+        let subject_ids_to_group_ids: HashMap<u32, u32> =
+            [(1, 101), (2, 102), (3, 103)].iter().cloned().collect();
+        let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> =
+            [(101, vec![1, 2]), (102, vec![])].iter().cloned().collect();
+        let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+        assert_eq!(
+            vec![
+                Violation::NotListedAsMember {
+                    subject_id: 2,
+                    group_id: 102
+                },
+                Violation::NotListedAsMember {
+                    subject_id: 3,
+                    group_id: 103
+                },
+                Violation::NotMappedToGroup {
+                    subject_id: 2,
+                    group_id: 101
+                },
+                Violation::UnknownSubject { subject_id: 3 },
+                Violation::UnknownGroup { group_id: 103 },
+            ],
+            assignment.validate(&subjects, &groups)
+        );
+    }
+
+    #[test]
+    fn validate_orders_by_kind() {
+        let subjects = [TestSubject::new(1, vec![]), TestSubject::new(2, vec![])];
+        let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 5)];
+        let subject_ids_to_group_ids: HashMap<u32, u32> =
+            [(1, 101), (2, 101)].iter().cloned().collect();
+        let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> = [(101, vec![1, 2]), (102, vec![2])]
+            .iter()
+            .cloned()
+            .collect();
+        let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+        // The violations are found in the order OverCapacity, NotMappedToGroup, MultipleMemberships, but reported by kind
+        assert_eq!(
+            vec![
+                Violation::NotMappedToGroup {
+                    subject_id: 2,
+                    group_id: 102
+                },
+                Violation::MultipleMemberships {
+                    subject_id: 2,
+                    group_ids: vec![101, 102]
+                },
+                Violation::OverCapacity {
+                    group_id: 101,
+                    members: 2,
+                    capacity: 1
+                },
+            ],
+            assignment.validate(&subjects, &groups)
+        );
+    }
+
+    #[test]
+    fn validate_unlimited_capacity() {
+        let subjects = [TestSubject::new(1, vec![])];
+        let groups = [DefaultGroup::unlimited(101)];
+        let subject_ids_to_group_ids: HashMap<u32, u32> =
+            [(1, 101), (2, 101)].iter().cloned().collect();
+        let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> =
+            [(101, vec![1, 2])].iter().cloned().collect();
+        let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+        // Listing more members than there are subjects does not exceed an unlimited capacity
+        assert_eq!(
+            vec![Violation::UnknownSubject { subject_id: 2 }],
+            assignment.validate(&subjects, &groups)
+        );
+    }
+
+    #[test]
+    fn validate_multiple_memberships() {
+        let subjects = [TestSubject::new(1, vec![])];
+        let groups = [DefaultGroup::new(101, 5), DefaultGroup::new(102, 5)];
+        let subject_ids_to_group_ids: HashMap<u32, u32> = [(1, 101)].iter().cloned().collect();
+        let group_ids_to_subjects_ids: HashMap<u32, Vec<u32>> =
+            [(101, vec![1]), (102, vec![1])].iter().cloned().collect();
+        let assignment = Assignment::from((subject_ids_to_group_ids, group_ids_to_subjects_ids));
+        assert_eq!(
+            vec![
+                Violation::NotMappedToGroup {
+                    subject_id: 1,
+                    group_id: 102
+                },
+                Violation::MultipleMemberships {
+                    subject_id: 1,
+                    group_ids: vec![101, 102]
+                },
+            ],
+            assignment.validate(&subjects, &groups)
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! # Waitlists
+//! This module turns a one-shot [assignment](crate::Assignment) into an enrollment that can be maintained while subjects withdraw.
+//!
+//! Every subject is on the waitlist of each group it prefers to the group it is assigned to (or of every group if it is not assigned at all).
+//! Whenever a seat becomes available the group promotes a subject from its waitlist, whose previous seat then becomes available in turn.
+use super::errors::{self, AssignmentError};
+use super::Assignment;
+use crate::groups::{effective_capacity, unseated, Group, SeatReservation};
+use crate::subjects::{DefaultSubject, Subject};
+use std::collections::HashMap;
+
+/// Decides which waitlisted subject is promoted when a seat becomes available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WaitlistPolicy {
+    /// The subject whose dissatisfaction decreases the most is promoted first. Unassigned subjects come before everyone else.
+    DissatisfactionImprovement,
+    /// Subjects are promoted in the order they were given in.
+    SubjectOrder,
+}
+
+/// A subject that took an available seat in a group it prefers to its previous group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Promotion {
+    subject_id: u32,
+    from_group_id: Option<u32>,
+    to_group_id: u32,
+}
+
+impl Promotion {
+    /// The id of the promoted subject.
+    pub fn subject_id(&self) -> u32 {
+        self.subject_id
+    }
+
+    /// The id of the group the subject was assigned to before the promotion, or None if it was not assigned.
+    pub fn from_group_id(&self) -> Option<u32> {
+        self.from_group_id
+    }
+
+    /// The id of the group the subject was promoted to.
+    pub fn to_group_id(&self) -> u32 {
+        self.to_group_id
+    }
+}
+
+/// An assignment together with the waitlists of its groups.
+///
+/// ```
+/// use group_assignment::{DefaultGroup, DefaultSubject, Subject};
+/// use group_assignment::assigners::{Assigner, FirstComeFirstServed};
+/// use group_assignment::waitlists::{Enrollment, WaitlistPolicy};
+/// use std::collections::HashMap;
+/// let prefer_first: HashMap<u32, u32> = [(101, 0), (102, 1)].iter().cloned().collect();
+/// let subjects = [
+///     DefaultSubject::new(1, prefer_first.clone(), 2),
+///     DefaultSubject::new(2, prefer_first, 2),
+/// ];
+/// let groups = [DefaultGroup::new(101, 1), DefaultGroup::new(102, 1)];
+/// let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+/// let mut enrollment = Enrollment::new(assignment, &subjects, &groups, WaitlistPolicy::SubjectOrder).unwrap();
+/// assert_eq!(vec![2], enrollment.waitlist(&groups[0]));
+/// // When the first subject withdraws the second subject takes its seat
+/// let promotions = enrollment.withdraw(subjects[0].id());
+/// assert_eq!(1, promotions.len());
+/// assert_eq!(Some(&101), enrollment.assignment().subject_to_group_id(&subjects[1]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Enrollment {
+    assignment: Assignment,
+    policy: WaitlistPolicy,
+    // The groups' ids and capacities in the order they were given in
+    groups: Vec<(u32, usize)>,
+    // The ids of the subjects that have not withdrawn, in the order they were given in
+    subject_ids: Vec<u32>,
+    dissatisfaction: HashMap<(u32, u32), u32>,
+    // The reserved seats of the groups with limited capacity that have some
+    reserved_seats: HashMap<u32, Vec<SeatReservation>>,
+    // The subjects' categories with respect to the attributes of the reserved seats, needed to tell who is eligible for them
+    categories: HashMap<u32, HashMap<u32, u32>>,
+}
+
+impl Enrollment {
+    /// Creates an enrollment from an assignment of the given subjects to the given groups.
+    /// Available seats in groups with non-empty waitlists are filled right away.
+    /// An error is returned if the subject ids or the group ids are not unique.
+    pub fn new<S: Subject, G: Group>(
+        assignment: Assignment,
+        subjects: &[S],
+        groups: &[G],
+        policy: WaitlistPolicy,
+    ) -> Result<Self, AssignmentError> {
+        errors::unique_ids(
+            subjects.iter().map(|s| s.id()),
+            groups.iter().map(|g| g.id()),
+        )?;
+        let dissatisfaction = subjects
+            .iter()
+            .flat_map(|s| {
+                groups
+                    .iter()
+                    .map(move |g| ((s.id(), g.id()), s.dissatisfaction(&g.id())))
+            })
+            .collect();
+        let reserved_seats: HashMap<u32, Vec<SeatReservation>> = groups
+            .iter()
+            .filter(|g| !g.has_unlimited_capacity())
+            .map(|g| (g.id(), g.reserved_seats()))
+            .filter(|(_, reservations)| !reservations.is_empty())
+            .collect();
+        let attributes: Vec<u32> = reserved_seats
+            .values()
+            .flatten()
+            .map(|x| x.attribute())
+            .collect();
+        let categories = subjects
+            .iter()
+            .filter(|_| !attributes.is_empty())
+            .map(|s| {
+                let categories = attributes
+                    .iter()
+                    .filter_map(|attribute| s.category(attribute).map(|c| (*attribute, c)))
+                    .collect();
+                (s.id(), categories)
+            })
+            .collect();
+        let mut enrollment = Self {
+            assignment,
+            policy,
+            groups: groups
+                .iter()
+                .map(|g| (g.id(), effective_capacity(g, subjects.len())))
+                .collect(),
+            subject_ids: subjects.iter().map(|s| s.id()).collect(),
+            dissatisfaction,
+            reserved_seats,
+            categories,
+        };
+        let group_ids: Vec<u32> = enrollment.groups.iter().map(|(id, _)| *id).collect();
+        enrollment.promote(group_ids);
+        Ok(enrollment)
+    }
+
+    /// The current assignment.
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+
+    /// The ids of the subjects waiting for a seat in the given group, in the order they would be promoted in.
+    ///
+    /// Seats [reserved](crate::Group::reserved_seats) for a category go to the first subject on the waitlist who is eligible for them.
+    /// Only when no such subject is waiting is a reserved seat released to the head of the waitlist.
+    pub fn waitlist<G: Group>(&self, group: &G) -> Vec<u32> {
+        self.waitlist_by_id(group.id())
+    }
+
+    /// Removes the subject with the given id from the enrollment and fills the seat it leaves behind.
+    /// Returns the resulting promotions in the order they took place.
+    pub fn withdraw(&mut self, subject_id: u32) -> Vec<Promotion> {
+        self.subject_ids.retain(|x| *x != subject_id);
+        match self.assignment.unassign_subject(subject_id) {
+            Some(group_id) => self.promote(vec![group_id]),
+            None => Vec::new(),
+        }
+    }
+
+    fn waitlist_by_id(&self, group_id: u32) -> Vec<u32> {
+        if !self.groups.iter().any(|(id, _)| *id == group_id) {
+            return Vec::new();
+        }
+        // Subjects with larger improvements come first, where unassigned subjects have improvement None which we treat as the largest
+        let mut waitlist: Vec<(Option<u32>, u32)> = self
+            .subject_ids
+            .iter()
+            .filter_map(|subject_id| {
+                let wanted = self.dissatisfaction[&(*subject_id, group_id)];
+                // Subjects assigned to groups outside of the enrollment are treated as unassigned
+                match self
+                    .assignment
+                    .subject_ids_to_group_ids
+                    .get(subject_id)
+                    .and_then(|current| self.dissatisfaction.get(&(*subject_id, *current)))
+                {
+                    None => Some((None, *subject_id)),
+                    Some(current) => {
+                        (wanted < *current).then(|| (Some(current - wanted), *subject_id))
+                    }
+                }
+            })
+            .collect();
+        if self.policy == WaitlistPolicy::DissatisfactionImprovement {
+            // A stable sort keeps subjects with equal improvement in the order they were given in
+            waitlist.sort_by(|(x, _), (y, _)| match (x, y) {
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (Some(x), Some(y)) => y.cmp(x),
+            });
+        }
+        waitlist.into_iter().map(|(_, id)| id).collect()
+    }
+
+    // Fills the available seats of the given groups and of every group a promoted subject leaves behind.
+    fn promote(&mut self, mut groups_with_available_seats: Vec<u32>) -> Vec<Promotion> {
+        let mut promotions = Vec::new();
+        let capacities: HashMap<u32, usize> = self.groups.iter().cloned().collect();
+        groups_with_available_seats.reverse();
+        while let Some(group_id) = groups_with_available_seats.pop() {
+            let members = self
+                .assignment
+                .group_ids_to_subjects_ids
+                .get(&group_id)
+                .map_or(0, |x| x.len());
+            if capacities.get(&group_id).is_none_or(|c| members >= *c) {
+                continue;
+            }
+            if let Some(subject_id) = self.next_promotion(group_id) {
+                let from_group_id = self.assignment.unassign_subject(subject_id);
+                self.assignment.assign_subject(subject_id, group_id);
+                promotions.push(Promotion {
+                    subject_id,
+                    from_group_id,
+                    to_group_id: group_id,
+                });
+                // The group may have more available seats and the previous group now has one
+                groups_with_available_seats.push(group_id);
+                groups_with_available_seats.extend(from_group_id);
+            }
+        }
+        promotions
+    }
+
+    // The waitlisted subject to be promoted to an available seat of the given group.
+    // Subjects who would leave more members without a seat they are eligible for are passed over, unless nobody else is waiting.
+    fn next_promotion(&self, group_id: u32) -> Option<u32> {
+        let waitlist = self.waitlist_by_id(group_id);
+        let head = waitlist.first().copied();
+        let Some(reservations) = self.reserved_seats.get(&group_id) else {
+            return head;
+        };
+        let capacity = self
+            .groups
+            .iter()
+            .find(|(id, _)| *id == group_id)
+            .map_or(0, |(_, capacity)| *capacity);
+        let reserved = reservations
+            .iter()
+            .fold(0_usize, |acc, x| acc.saturating_add(x.seats() as usize));
+        let general_seats = capacity.saturating_sub(reserved);
+        // Subjects not given to the enrollment count as belonging to no category
+        let profile = |subject_id: u32| {
+            DefaultSubject::new(subject_id, HashMap::new(), 0).with_categories(
+                self.categories
+                    .get(&subject_id)
+                    .cloned()
+                    .unwrap_or_default(),
+            )
+        };
+        let members: Vec<DefaultSubject> = self
+            .assignment
+            .group_ids_to_subjects_ids
+            .get(&group_id)
+            .map_or(Vec::new(), |x| x.iter().map(|id| profile(*id)).collect());
+        let seated: Vec<&DefaultSubject> = members.iter().collect();
+        let unseated_members = unseated(&seated, reservations, general_seats);
+        waitlist
+            .into_iter()
+            .find(|subject_id| {
+                let candidate = profile(*subject_id);
+                let with_candidate: Vec<&DefaultSubject> = seated
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(&candidate))
+                    .collect();
+                unseated(&with_candidate, reservations, general_seats) <= unseated_members
+            })
+            .or(head)
+    }
+}
+
+impl From<Enrollment> for Assignment {
+    fn from(enrollment: Enrollment) -> Self {
+        enrollment.assignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment::assigners::{Assigner, FirstComeFirstServed};
+    use crate::groups::DefaultGroup;
+    use crate::subjects::test_utils::TestSubject;
+
+    fn setup() -> ([TestSubject; 3], [DefaultGroup; 3]) {
+        let group_ids = [101_u32, 102, 103];
+        let subjects = [
+            TestSubject::new(1, vec![group_ids[0]]),
+            TestSubject::new(2, vec![group_ids[0], group_ids[1]]),
+            TestSubject::new(3, vec![group_ids[0], group_ids[1], group_ids[2]]),
+        ];
+        let groups = [
+            DefaultGroup::new(group_ids[0], 1),
+            DefaultGroup::new(group_ids[1], 1),
+            DefaultGroup::new(group_ids[2], 1),
+        ];
+        (subjects, groups)
+    }
+
+    #[test]
+    fn withdraw_cascades_promotions_in_subject_order() {
+        let (subjects, groups) = setup();
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let mut enrollment =
+            Enrollment::new(assignment, &subjects, &groups, WaitlistPolicy::SubjectOrder).unwrap();
+        assert_eq!(vec![2, 3], enrollment.waitlist(&groups[0]));
+        assert_eq!(vec![3], enrollment.waitlist(&groups[1]));
+        let promotions = enrollment.withdraw(1);
+        assert_eq!(
+            vec![
+                Promotion {
+                    subject_id: 2,
+                    from_group_id: Some(102),
+                    to_group_id: 101
+                },
+                Promotion {
+                    subject_id: 3,
+                    from_group_id: Some(103),
+                    to_group_id: 102
+                }
+            ],
+            promotions
+        );
+        // The third subject still prefers the first group
+        assert_eq!(vec![3], enrollment.waitlist(&groups[0]));
+        assert!(enrollment.waitlist(&groups[2]).is_empty());
+    }
+
+    #[test]
+    fn withdraw_promotes_largest_improvement_first() {
+        let (subjects, groups) = setup();
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let mut enrollment = Enrollment::new(
+            assignment,
+            &subjects,
+            &groups,
+            WaitlistPolicy::DissatisfactionImprovement,
+        )
+        .unwrap();
+        assert_eq!(vec![3, 2], enrollment.waitlist(&groups[0]));
+        let promotions = enrollment.withdraw(1);
+        // Nobody is waiting for the seat the third subject leaves behind
+        assert_eq!(1, promotions.len());
+        assert_eq!(
+            Some(&101),
+            enrollment.assignment().subject_to_group_id(&subjects[2])
+        );
+        assert_eq!(
+            Some(&102),
+            enrollment.assignment().subject_to_group_id(&subjects[1])
+        );
+    }
+
+    #[test]
+    fn new_rejects_duplicate_ids() {
+        let (subjects, groups) = setup();
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let duplicates = [
+            TestSubject::new(1, vec![101]),
+            TestSubject::new(1, vec![102]),
+        ];
+        let result = Enrollment::new(
+            assignment,
+            &duplicates,
+            &groups,
+            WaitlistPolicy::SubjectOrder,
+        );
+        assert!(matches!(result, Err(AssignmentError::DuplicateIds { .. })));
+    }
+
+    #[test]
+    fn withdraw_keeps_reserved_seat_for_eligible_subject() {
+        use crate::groups::SeatReservation;
+        use crate::subjects::DefaultSubject;
+        let preferences: HashMap<u32, u32> = [(101, 0), (102, 1)].iter().cloned().collect();
+        let subject = |id: u32, eligible: bool| {
+            let categories = if eligible {
+                [(1, 7)].iter().cloned().collect()
+            } else {
+                HashMap::new()
+            };
+            DefaultSubject::new(id, preferences.clone(), 2).with_categories(categories)
+        };
+        let subjects = [
+            subject(1, true),
+            subject(2, false),
+            subject(3, false),
+            subject(4, true),
+        ];
+        let groups = [
+            DefaultGroup::new(101, 2).with_reserved_seats(vec![SeatReservation::new(1, 7, 1)]),
+            DefaultGroup::new(102, 2),
+        ];
+        let assignment = FirstComeFirstServed::assign(&subjects, &groups).unwrap();
+        let mut enrollment =
+            Enrollment::new(assignment, &subjects, &groups, WaitlistPolicy::SubjectOrder).unwrap();
+        assert_eq!(vec![3, 4], enrollment.waitlist(&groups[0]));
+        // The seat reserved for the first subject's category goes to the fourth subject, although the third is ahead of it
+        let promotions = enrollment.withdraw(1);
+        assert_eq!(1, promotions.len());
+        assert_eq!(4, promotions[0].subject_id());
+        // Without eligible subjects waiting the seat is released
+        let promotions = enrollment.withdraw(4);
+        assert_eq!(3, promotions[0].subject_id());
+    }
+}
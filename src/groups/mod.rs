@@ -1,6 +1,15 @@
 //! # Groups
 //! This module introduces the group trait
 
+mod quotas;
+mod reservations;
+pub use quotas::{Quota, QuotaBound, QuotaViolation};
+pub use reservations::SeatReservation;
+pub(crate) use reservations::{seats_suffice, unseated};
+
+/// The capacity of groups that may take any number of subjects.
+pub const UNLIMITED_CAPACITY: u32 = u32::MAX;
+
 /// The groups the subjects may choose from must implement this trait.
 pub trait Group {
     /// The group's id. Every binding to a type implementing the group trait is expected to have a unique id.
@@ -8,18 +17,95 @@ pub trait Group {
     /// We do not require the images of this map and the equally named function in the [subject trait](crate::subjects::Subject) to be disjoint.  
     fn id(&self) -> u32;
 
-    /// The groups capacity
+    /// The groups capacity. Groups that may take any number of subjects return [UNLIMITED_CAPACITY].
     fn capacity(&self) -> u32;
+
+    /// Indicates whether the group may take any number of subjects.
+    fn has_unlimited_capacity(&self) -> bool {
+        self.capacity() == UNLIMITED_CAPACITY
+    }
+
+    /// Constraints on the composition of the group. Groups without such constraints may rely on the default implementation.
+    ///
+    /// Only some assigners (such as [QuotaRespecting](crate::assigners::QuotaRespecting)) take quotas into account.
+    fn quotas(&self) -> Vec<Quota> {
+        Vec::new()
+    }
+
+    /// Seats of the group's capacity that are reserved for subjects of a certain category. Groups without reserved seats may rely on the default implementation.
+    ///
+    /// Reserved seats are honoured by [FirstComeFirstServed](crate::assigners::FirstComeFirstServed), [ImmediateAcceptance](crate::assigners::ImmediateAcceptance),
+    /// [Draft](crate::assigners::Draft) and [ProposeAndReject](crate::assigners::ProposeAndReject), which release the seats that remain unused to everyone.
+    /// The other assigners and [ProbabilisticSerial](crate::lotteries::ProbabilisticSerial) reject groups with reserved seats as invalid input.
+    fn reserved_seats(&self) -> Vec<SeatReservation> {
+        Vec::new()
+    }
 }
+
+// The number of seats the group offers to the given number of subjects, which is all of them for groups with unlimited capacity.
+pub(crate) fn effective_capacity<G: Group>(group: &G, number_of_subjects: usize) -> usize {
+    if group.has_unlimited_capacity() {
+        number_of_subjects
+    } else {
+        group.capacity() as usize
+    }
+}
+
 /// A simple group type.
 pub struct DefaultGroup {
     id: u32,
     capacity: u32,
+    quotas: Vec<Quota>,
+    reserved_seats: Vec<SeatReservation>,
 }
 
 impl DefaultGroup {
     pub fn new(id: u32, capacity: u32) -> Self {
-        DefaultGroup { id, capacity }
+        DefaultGroup {
+            id,
+            capacity,
+            quotas: Vec::new(),
+            reserved_seats: Vec::new(),
+        }
+    }
+
+    /// A group that may take any number of subjects.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, Group};
+    /// let group = DefaultGroup::unlimited(101);
+    /// assert!(group.has_unlimited_capacity());
+    /// ```
+    pub fn unlimited(id: u32) -> Self {
+        Self::new(id, UNLIMITED_CAPACITY)
+    }
+
+    /// Equips the group with constraints on its composition.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, Group, Quota};
+    /// let seniority = 1_u32;
+    /// let senior = 2_u32;
+    /// let group = DefaultGroup::new(101, 5).with_quotas(vec![Quota::at_least(seniority, senior, 1)]);
+    /// assert_eq!(1, group.quotas().len());
+    /// ```
+    pub fn with_quotas(mut self, quotas: Vec<Quota>) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Reserves some of the group's seats for subjects of certain categories.
+    ///
+    /// ```
+    /// use group_assignment::{DefaultGroup, Group, SeatReservation};
+    /// let status = 1_u32;
+    /// let exchange_student = 3_u32;
+    /// let group = DefaultGroup::new(101, 30).with_reserved_seats(vec![SeatReservation::new(status, exchange_student, 5)]);
+    /// assert_eq!(5, group.reserved_seats()[0].seats());
+    /// ```
+    pub fn with_reserved_seats(mut self, reserved_seats: Vec<SeatReservation>) -> Self {
+        self.reserved_seats = reserved_seats;
+        self
     }
 }
 
@@ -31,4 +117,12 @@ impl Group for DefaultGroup {
     fn capacity(&self) -> u32 {
         self.capacity
     }
+
+    fn quotas(&self) -> Vec<Quota> {
+        self.quotas.clone()
+    }
+
+    fn reserved_seats(&self) -> Vec<SeatReservation> {
+        self.reserved_seats.clone()
+    }
 }
@@ -0,0 +1,197 @@
+//! # Quotas
+//! This module introduces constraints on the composition of a group with respect to the [categories](crate::Subject::category) of its members.
+
+/// Bounds on how many of a group's members may belong to a certain category. All bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum QuotaBound {
+    /// Bounds on the number of members belonging to the category.
+    Count { min: u32, max: u32 },
+    /// Bounds on the proportion (a number between 0 and 1) of the members belonging to the category.
+    Proportion { min: f64, max: f64 },
+}
+
+/// A constraint on the number of members of a group that belong to a certain category with respect to a given attribute.
+///
+/// Quotas constrain the composition of a group and thus only apply to groups with at least one member.
+///
+/// ```
+/// use group_assignment::{Quota, QuotaBound};
+/// let department = 1_u32;
+/// let physics = 7_u32;
+/// // No more than 60% of the group's members may come from the physics department
+/// let quota = Quota::new(department, physics, QuotaBound::Proportion { min: 0.0, max: 0.6 });
+/// assert_eq!(quota.category(), physics);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quota {
+    attribute: u32,
+    category: u32,
+    bound: QuotaBound,
+}
+
+impl Quota {
+    /// Constructor
+    pub fn new(attribute: u32, category: u32, bound: QuotaBound) -> Self {
+        Self {
+            attribute,
+            category,
+            bound,
+        }
+    }
+
+    /// Requires at least `min` members belonging to the category.
+    pub fn at_least(attribute: u32, category: u32, min: u32) -> Self {
+        Self::new(
+            attribute,
+            category,
+            QuotaBound::Count { min, max: u32::MAX },
+        )
+    }
+
+    /// Allows at most `max` members belonging to the category.
+    pub fn at_most(attribute: u32, category: u32, max: u32) -> Self {
+        Self::new(attribute, category, QuotaBound::Count { min: 0, max })
+    }
+
+    /// The attribute the quota refers to.
+    pub fn attribute(&self) -> u32 {
+        self.attribute
+    }
+
+    /// The category (with respect to the attribute) the quota refers to.
+    pub fn category(&self) -> u32 {
+        self.category
+    }
+
+    /// The bounds imposed by the quota.
+    pub fn bound(&self) -> QuotaBound {
+        self.bound
+    }
+
+    /// Indicates whether the lower bound does not exceed the upper bound and proportions lie between 0 and 1.
+    pub(crate) fn is_sensible(&self) -> bool {
+        match self.bound {
+            QuotaBound::Count { min, max } => min <= max,
+            QuotaBound::Proportion { min, max } => {
+                (0.0..=1.0).contains(&min) && (0.0..=1.0).contains(&max) && min <= max
+            }
+        }
+    }
+
+    /// Returns (deficit, excess): the number of members of the category that are missing, respectively
+    /// superfluous, for a group of `members` members of which `members_in_category` belong to the category.
+    /// At most one of the two numbers is positive for any sensible quota.
+    pub(crate) fn deviation(&self, members_in_category: u32, members: u32) -> (u32, u32) {
+        if members == 0 {
+            return (0, 0);
+        }
+        let (min, max) = match self.bound {
+            QuotaBound::Count { min, max } => (min, max),
+            QuotaBound::Proportion { min, max } => {
+                // Allow for rounding errors such as 0.6 * 5 = 2.9999999999999996
+                let epsilon = 1e-9;
+                let members = members as f64;
+                (
+                    (min * members - epsilon).ceil().max(0.0) as u32,
+                    (max * members + epsilon).floor().max(0.0) as u32,
+                )
+            }
+        };
+        (
+            min.saturating_sub(members_in_category),
+            members_in_category.saturating_sub(max),
+        )
+    }
+}
+
+/// Describes a group whose composition does not satisfy one of its [quotas](Quota).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaViolation {
+    group_id: u32,
+    quota: Quota,
+    members_in_category: u32,
+    members: u32,
+}
+
+impl QuotaViolation {
+    pub(crate) fn new(group_id: u32, quota: Quota, members_in_category: u32, members: u32) -> Self {
+        Self {
+            group_id,
+            quota,
+            members_in_category,
+            members,
+        }
+    }
+
+    /// The id of the group whose quota is violated.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// The violated quota.
+    pub fn quota(&self) -> &Quota {
+        &self.quota
+    }
+
+    /// The number of the group's members belonging to the quota's category.
+    pub fn members_in_category(&self) -> u32 {
+        self.members_in_category
+    }
+
+    /// The total number of members of the group.
+    pub fn members(&self) -> u32 {
+        self.members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deviation_count() {
+        let quota = Quota::new(1, 2, QuotaBound::Count { min: 1, max: 3 });
+        assert_eq!((1, 0), quota.deviation(0, 4));
+        assert_eq!((0, 0), quota.deviation(2, 4));
+        assert_eq!((0, 1), quota.deviation(4, 4));
+        // Quotas do not apply to empty groups
+        assert_eq!((0, 0), quota.deviation(0, 0));
+    }
+
+    #[test]
+    fn deviation_proportion() {
+        let quota = Quota::new(1, 2, QuotaBound::Proportion { min: 0.2, max: 0.6 });
+        assert_eq!((0, 0), quota.deviation(3, 5));
+        assert_eq!((0, 1), quota.deviation(4, 5));
+        assert_eq!((1, 0), quota.deviation(0, 5));
+        assert_eq!((1, 0), quota.deviation(0, 2));
+    }
+
+    #[test]
+    fn is_sensible() {
+        assert!(Quota::new(1, 2, QuotaBound::Count { min: 2, max: 2 }).is_sensible());
+        assert!(!Quota::new(1, 2, QuotaBound::Count { min: 3, max: 2 }).is_sensible());
+        assert!(Quota::new(1, 2, QuotaBound::Proportion { min: 0.0, max: 1.0 }).is_sensible());
+        assert!(!Quota::new(1, 2, QuotaBound::Proportion { min: 0.6, max: 0.4 }).is_sensible());
+        assert!(!Quota::new(
+            1,
+            2,
+            QuotaBound::Proportion {
+                min: -0.1,
+                max: 0.4
+            }
+        )
+        .is_sensible());
+        assert!(!Quota::new(1, 2, QuotaBound::Proportion { min: 0.0, max: 1.5 }).is_sensible());
+        assert!(!Quota::new(
+            1,
+            2,
+            QuotaBound::Proportion {
+                min: f64::NAN,
+                max: 1.0
+            }
+        )
+        .is_sensible());
+    }
+}
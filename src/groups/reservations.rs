@@ -0,0 +1,152 @@
+//! # Reservations
+//! This module introduces seats of a group that are reserved for subjects of a certain [category](crate::Subject::category).
+use crate::subjects::Subject;
+
+/// A number of seats of a group reserved for subjects belonging to a certain category with respect to a given attribute.
+///
+/// The reserved seats are part of the group's [capacity](crate::Group::capacity). Assigners supporting reservations first fill the reserved seats with eligible subjects
+/// and release the seats that remain unused to everyone afterwards.
+///
+/// ```
+/// use group_assignment::{DefaultSubject, SeatReservation};
+/// use std::collections::HashMap;
+/// let status = 1_u32;
+/// let exchange_student = 3_u32;
+/// // Five seats are reserved for exchange students
+/// let reservation = SeatReservation::new(status, exchange_student, 5);
+/// let categories: HashMap<u32, u32> = [(status, exchange_student)].iter().cloned().collect();
+/// let subject = DefaultSubject::new(1, HashMap::new(), 0).with_categories(categories);
+/// assert!(reservation.admits(&subject));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatReservation {
+    attribute: u32,
+    category: u32,
+    seats: u32,
+}
+
+impl SeatReservation {
+    /// Constructor
+    pub fn new(attribute: u32, category: u32, seats: u32) -> Self {
+        Self {
+            attribute,
+            category,
+            seats,
+        }
+    }
+
+    /// The attribute the reservation refers to.
+    pub fn attribute(&self) -> u32 {
+        self.attribute
+    }
+
+    /// The category (with respect to the attribute) the seats are reserved for.
+    pub fn category(&self) -> u32 {
+        self.category
+    }
+
+    /// The number of reserved seats.
+    pub fn seats(&self) -> u32 {
+        self.seats
+    }
+
+    /// Indicates whether the given subject is eligible for the reserved seats.
+    pub fn admits<S: Subject>(&self, subject: &S) -> bool {
+        subject.category(&self.attribute) == Some(self.category)
+    }
+}
+
+// Indicates whether the given subjects can all be seated, each either in a reserved seat it is eligible for or in one of the general seats.
+pub(crate) fn seats_suffice<S: Subject>(
+    subjects: &[&S],
+    reservations: &[SeatReservation],
+    general_seats: usize,
+) -> bool {
+    unseated(subjects, reservations, general_seats) == 0
+}
+
+// The number of the given subjects left without a seat when as many as possible are seated in reserved seats they are eligible for and the rest in the general seats.
+// Subjects are matched to reserved seats along augmenting paths, so that a subject eligible for several reservations does not block the seats of another one.
+pub(crate) fn unseated<S: Subject>(
+    subjects: &[&S],
+    reservations: &[SeatReservation],
+    general_seats: usize,
+) -> usize {
+    if subjects.len() <= general_seats {
+        return 0;
+    }
+    // occupants[r] holds the indices of the subjects seated in the r'th reservation
+    let mut occupants: Vec<Vec<usize>> = vec![Vec::new(); reservations.len()];
+    let mut seated = 0;
+    for i in 0..subjects.len() {
+        let mut visited = vec![false; reservations.len()];
+        if augment(i, subjects, reservations, &mut occupants, &mut visited) {
+            seated += 1;
+        }
+    }
+    (subjects.len() - seated).saturating_sub(general_seats)
+}
+
+// Seats the i'th subject in a reservation it is eligible for, moving other subjects to other reservations if need be.
+fn augment<S: Subject>(
+    i: usize,
+    subjects: &[&S],
+    reservations: &[SeatReservation],
+    occupants: &mut [Vec<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for (r, reservation) in reservations.iter().enumerate() {
+        if visited[r] || !reservation.admits(subjects[i]) {
+            continue;
+        }
+        visited[r] = true;
+        if occupants[r].len() < reservation.seats() as usize {
+            occupants[r].push(i);
+            return true;
+        }
+        for k in 0..occupants[r].len() {
+            if augment(occupants[r][k], subjects, reservations, occupants, visited) {
+                occupants[r][k] = i;
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subjects::DefaultSubject;
+    use std::collections::HashMap;
+
+    fn subject(id: u32, categories: &[(u32, u32)]) -> DefaultSubject {
+        DefaultSubject::new(id, HashMap::new(), 0)
+            .with_categories(categories.iter().cloned().collect())
+    }
+
+    #[test]
+    fn seats_suffice_overlapping_reservations() {
+        let reservations = [SeatReservation::new(1, 7, 1), SeatReservation::new(2, 8, 1)];
+        let both = subject(1, &[(1, 7), (2, 8)]);
+        let first_only = subject(2, &[(1, 7)]);
+        let neither = subject(3, &[]);
+        // The subject eligible for both reservations moves to the second one to make room
+        assert!(seats_suffice(&[&both, &first_only], &reservations, 0));
+        assert!(!seats_suffice(
+            &[&both, &first_only, &neither],
+            &reservations,
+            0
+        ));
+        assert!(seats_suffice(
+            &[&both, &first_only, &neither],
+            &reservations,
+            1
+        ));
+        assert!(!seats_suffice(
+            &[&first_only, &first_only],
+            &reservations,
+            0
+        ));
+    }
+}
@@ -145,9 +145,20 @@ mod assignment;
 mod groups;
 mod subjects;
 pub use assignment::assigners;
+pub use assignment::comparison;
+pub use assignment::efficiency;
+pub use assignment::envy;
 pub use assignment::errors;
+pub use assignment::improvement;
+pub use assignment::lotteries;
+pub use assignment::pairing;
+pub use assignment::simulation;
+pub use assignment::teams;
+pub use assignment::validation;
+pub use assignment::waitlists;
 pub use assignment::Assignment;
 pub use groups::DefaultGroup;
 pub use groups::Group;
+pub use groups::{Quota, QuotaBound, QuotaViolation, SeatReservation, UNLIMITED_CAPACITY};
 pub use subjects::DefaultSubject;
 pub use subjects::Subject;
@@ -13,6 +13,27 @@ pub trait Subject {
     ///
     /// We do not require the images of this map and the equally named function in the [group trait](crate::groups::Group) to be disjoint.
     fn id(&self) -> u32;
+
+    /// The subject's category with respect to the given attribute (for instance the department the subject belongs to).
+    /// Subjects without categorical attributes may rely on the default implementation which returns None for every attribute.
+    fn category(&self, _attribute: &u32) -> Option<u32> {
+        None
+    }
+
+    /// How much the subject would like to be in the same group as the subject with the given id. Higher values indicate a stronger affinity
+    /// and negative values indicate that the subject would rather not be grouped with the other subject.
+    ///
+    /// Only used when [forming teams](crate::teams). Subjects may rely on the default implementation which is indifferent to everyone.
+    fn affinity(&self, _subject_id: &u32) -> i32 {
+        0
+    }
+
+    /// The number of seats the subject needs, each in a different group.
+    ///
+    /// Only used when [drafting](crate::assigners::Draft::draft). Subjects may rely on the default implementation which demands a single seat.
+    fn demand(&self) -> u32 {
+        1
+    }
 }
 
 /// A simple subject type.
@@ -20,6 +41,9 @@ pub struct DefaultSubject {
     id: u32,
     preferences: HashMap<u32, u32>,
     default_dissatisfaction: u32,
+    categories: HashMap<u32, u32>,
+    affinities: HashMap<u32, i32>,
+    demand: u32,
 }
 impl DefaultSubject {
     /// Constructor
@@ -42,8 +66,61 @@ impl DefaultSubject {
             id,
             preferences,
             default_dissatisfaction,
+            categories: HashMap::new(),
+            affinities: HashMap::new(),
+            demand: 1,
         }
     }
+
+    /// Equips the subject with categorical attributes given as a map from attributes to categories.
+    ///
+    /// ```
+    /// use group_assignment::Subject;
+    /// use group_assignment::DefaultSubject;
+    /// use std::collections::HashMap;
+    /// let department = 1_u32;
+    /// let physics = 7_u32;
+    /// let categories: HashMap<u32,u32> = [(department, physics)].iter().cloned().collect();
+    /// let subject = DefaultSubject::new(42, HashMap::new(), 0).with_categories(categories);
+    /// assert_eq!(Some(physics), subject.category(&department));
+    /// assert_eq!(None, subject.category(&2));
+    /// ```
+    pub fn with_categories(mut self, categories: HashMap<u32, u32>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    /// Equips the subject with affinities towards other subjects given as a map from subject ids to affinities.
+    /// The affinity towards subjects not present in the map is 0.
+    ///
+    /// ```
+    /// use group_assignment::Subject;
+    /// use group_assignment::DefaultSubject;
+    /// use std::collections::HashMap;
+    /// let affinities: HashMap<u32,i32> = [(2, 5), (3, -1)].iter().cloned().collect();
+    /// let subject = DefaultSubject::new(1, HashMap::new(), 0).with_affinities(affinities);
+    /// assert_eq!(5, subject.affinity(&2));
+    /// assert_eq!(0, subject.affinity(&4));
+    /// ```
+    pub fn with_affinities(mut self, affinities: HashMap<u32, i32>) -> Self {
+        self.affinities = affinities;
+        self
+    }
+
+    /// Sets the number of seats the subject needs, each in a different group.
+    ///
+    /// ```
+    /// use group_assignment::Subject;
+    /// use group_assignment::DefaultSubject;
+    /// use std::collections::HashMap;
+    /// let subject = DefaultSubject::new(1, HashMap::new(), 0);
+    /// assert_eq!(1, subject.demand());
+    /// assert_eq!(3, subject.with_demand(3).demand());
+    /// ```
+    pub fn with_demand(mut self, demand: u32) -> Self {
+        self.demand = demand;
+        self
+    }
 }
 impl Subject for DefaultSubject {
     fn id(&self) -> u32 {
@@ -56,6 +133,18 @@ impl Subject for DefaultSubject {
             .copied()
             .unwrap_or(self.default_dissatisfaction)
     }
+
+    fn category(&self, attribute: &u32) -> Option<u32> {
+        self.categories.get(attribute).copied()
+    }
+
+    fn affinity(&self, subject_id: &u32) -> i32 {
+        self.affinities.get(subject_id).copied().unwrap_or(0)
+    }
+
+    fn demand(&self) -> u32 {
+        self.demand
+    }
 }
 
 #[cfg(test)]
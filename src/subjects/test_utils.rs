@@ -4,6 +4,7 @@ use crate::subjects::Subject;
 pub(crate) struct TestSubject {
     id: u32,
     preferences: Vec<u32>,
+    #[allow(dead_code)]
     assigned_group_id: Option<u32>,
 }
 